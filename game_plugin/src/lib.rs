@@ -8,11 +8,15 @@ use bevy::prelude::*;
 
 use crate::plugins::loading::LoadingPlugin;
 use crate::plugins::menu::MenuPlugin;
+use crate::plugins::controls::ControlsMenuPlugin;
 use crate::plugins::input::InputPlugin;
 use crate::plugins::player::PlayerPlugin;
 use crate::plugins::actions::ActionsPlugin;
 use crate::plugins::physics::MyPhysicsPlugin;
+use crate::plugins::collisions::CollisionsPlugin;
 use crate::plugins::spectator::SpectatorCameraPlugin;
+use crate::plugins::character::CharacterPlugin;
+use crate::plugins::picking::PickingPlugin;
 use crate::plugins::console::ConsolePlugin;
 use crate::plugins::experimental::MyExperimentalPlugin;
 
@@ -25,6 +29,8 @@ enum GameState {
     Playing,
     // Here the menu is drawn and waiting for player interaction
     Menu,
+    // Here the player can rebind the controls, from the menu
+    Controls,
 }
 
 /// Top-level Plugin: wrapper for all other plugins.
@@ -39,6 +45,7 @@ impl Plugin for GamePlugin {
             //Game-specific Plugins
             .add_plugin(LoadingPlugin)
             .add_plugin(MenuPlugin)
+            .add_plugin(ControlsMenuPlugin)
             .add_plugin(PlayerPlugin)
             .add_plugin(InputPlugin)
             .add_plugin(ActionsPlugin)
@@ -47,6 +54,9 @@ impl Plugin for GamePlugin {
 
             // My Physics Plugin
             .add_plugin(MyPhysicsPlugin)
+            .add_plugin(CollisionsPlugin)
+            .add_plugin(CharacterPlugin)
+            .add_plugin(PickingPlugin)
 
             // My experimental plugin
             .add_plugin(MyExperimentalPlugin)
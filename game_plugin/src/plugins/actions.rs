@@ -1,4 +1,6 @@
 use bevy::app::{AppBuilder, Plugin};
+use bevy::math::Vec3;
+use bevy::render::color::Color;
 
 
 /// This Plugin registers Game Events, so that other systems can react to them.
@@ -17,7 +19,7 @@ impl Plugin for ActionsPlugin {
             .add_event::<SpawnCubeActor>()
             .add_event::<SpawnSpectatorCamera>()
 
-            .add_event::<GameActionBinding>()
+            .add_event::<GameAction>()
         ;
     }
 }
@@ -28,7 +30,7 @@ type AxisScale = f32;
 type IsEnabled = bool;
 
 
-// Mouse motion actions
+// Mouse motion actions: motion over the frame, in mouse counts (sticks are integrated over the frame)
 pub struct LookUp(pub AxisScale);
 pub struct LookRight(pub AxisScale);
 
@@ -43,7 +45,17 @@ pub struct Crouch(pub IsEnabled);
 pub struct Jump(pub IsEnabled);
 
 // Debug actions
-pub struct SpawnCubeActor;
+pub struct SpawnCubeActor {
+    pub position: Vec3,
+    /// A random color, unless specified
+    pub color: Option<Color>,
+}
+
+impl Default for SpawnCubeActor {
+    fn default() -> Self {
+        SpawnCubeActor { position: Vec3::new(0.0, 0.5, 0.0), color: None }
+    }
+}
 pub struct SpawnSpectatorCamera;
 
 pub struct ToggleConsole;
@@ -51,7 +63,26 @@ pub struct ToggleConsole;
 pub struct ExitGame;
 
 
-#[derive(serde::Serialize, serde::Deserialize, PartialEq, Clone, Copy, Debug)]
+/// Phase of a GameAction, relative to the previous frame
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum ActionPhase {
+    Started,
+    Held,
+    Released,
+}
+
+/// Unified action event, sent every frame for each binding that is pressed, held or released.
+///     Use `held_duration` for tap-vs-hold, double-tap or charge mechanics.
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub struct GameAction {
+    pub binding: GameActionBinding,
+    pub phase: ActionPhase,
+    pub held_duration: f32,
+    pub value: AxisScale,
+}
+
+
+#[derive(serde::Serialize, serde::Deserialize, PartialEq, Eq, Hash, Clone, Copy, Debug)]
 pub enum GameActionBinding {
     LookUp,
     LookRight,
@@ -70,4 +101,33 @@ pub enum GameActionBinding {
     ToggleConsole,
 
     ExitGame,
+
+    /// Runs the binding's console command, e.g. from `bind F5 "spawn cube"`
+    ConsoleCommand,
+}
+
+impl GameActionBinding {
+    /// Every action, in the order they are listed in the Controls menu.
+    ///     ConsoleCommand is not an action of its own: each of its bindings carries a different command.
+    pub const ALL: [GameActionBinding; 12] = [
+        GameActionBinding::LookUp,
+        GameActionBinding::LookRight,
+        GameActionBinding::MoveForward,
+        GameActionBinding::MoveBackward,
+        GameActionBinding::StrafeRight,
+        GameActionBinding::StrafeLeft,
+        GameActionBinding::Crouch,
+        GameActionBinding::Jump,
+        GameActionBinding::SpawnCubeActor,
+        GameActionBinding::SpawnSpectatorCamera,
+        GameActionBinding::ToggleConsole,
+        GameActionBinding::ExitGame,
+    ];
+
+    /// Case-insensitive lookup by variant name, e.g. `moveforward`
+    pub fn parse(name: &str) -> Option<GameActionBinding> {
+        GameActionBinding::ALL.iter()
+            .find(|it| format!("{:?}", it).eq_ignore_ascii_case(name))
+            .copied()
+    }
 }
@@ -0,0 +1,469 @@
+/// Walking character controller, driven by Heron rather than by writing `Transform.translation`:
+///     a capsule RigidBody whose horizontal Velocity accelerates towards the inputs, with ground detection,
+///     slope limits, jumping and crouching. The camera becomes the body's child, at eye height.
+///     `movemode walk` turns the Possessed Spectator Camera into a character; `movemode fly` turns it back.
+use bevy::prelude::*;
+use heron::prelude::*;
+use heron::rapier_plugin::PhysicsWorld;
+
+use crate::GameState;
+use crate::plugins::collisions::{CollisionGroup, Trigger};
+use crate::plugins::actions::{Crouch, Jump, MoveForward, StrafeRight};
+use crate::plugins::console::LogToConsole;
+use crate::plugins::console::commands::{AddConsoleCommand, ArgumentKind, ConsoleCommand};
+use crate::plugins::console::cvars::{AddCvar, Cvar, CvarValue, Cvars};
+use crate::plugins::console::parser::ArgumentError;
+use crate::plugins::input::FrameTime;
+use crate::plugins::player::Possessed;
+use crate::plugins::spectator::SpectatorCamera;
+use crate::utils::colliders::ToCollisionShape;
+
+
+/// Capsule dimensions: 1.8 units tall when standing, 1.0 when crouching
+const CAPSULE_RADIUS: f32 = 0.4;
+const STANDING_HALF_SEGMENT: f32 = 0.5;
+const CROUCHING_HALF_SEGMENT: f32 = 0.1;
+
+/// Height of the eyes above the capsule's center
+const STANDING_EYE_HEIGHT: f32 = 0.7;
+const CROUCHING_EYE_HEIGHT: f32 = 0.3;
+
+/// How far below the capsule the ground is still detected, so that bumps and steps don't count as falling
+const GROUND_TOLERANCE: f32 = 0.1;
+
+
+/// Plugins
+pub struct CharacterPlugin;
+
+impl Plugin for CharacterPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        let settings = CharacterSettings::default();
+        app
+            .add_event::<SetMovementMode>()
+            .init_resource::<CharacterSettings>()
+            .add_cvar(Cvar::new("walk_speed", CvarValue::Number(settings.speed),
+                "Character walking speed, in units per second")
+                .range(0.0, 100.0))
+            .add_cvar(Cvar::new("walk_acceleration", CvarValue::Number(settings.acceleration),
+                "Character acceleration towards the walking speed, in units per second squared")
+                .range(0.0, 1000.0))
+            .add_cvar(Cvar::new("walk_friction", CvarValue::Number(settings.friction),
+                "Character deceleration on the ground without inputs, in units per second squared")
+                .range(0.0, 1000.0))
+            .add_cvar(Cvar::new("walk_air_control", CvarValue::Number(settings.air_control),
+                "Share of the acceleration available while airborne")
+                .range(0.0, 1.0))
+            .add_cvar(Cvar::new("jump_speed", CvarValue::Number(settings.jump_speed),
+                "Vertical speed given by a jump, in units per second")
+                .range(0.0, 100.0))
+            .add_cvar(Cvar::new("max_slope", CvarValue::Number(settings.max_slope),
+                "Steepest walkable slope, in degrees")
+                .range(0.0, 89.0))
+            .add_cvar(Cvar::new("crouch_speed_factor", CvarValue::Number(settings.crouch_speed_factor),
+                "Share of the walking speed while crouching")
+                .range(0.0, 1.0))
+            .add_system(apply_cvars.system())
+            .add_console_command(ConsoleCommand::new("movemode",
+                "Switch the Possessed camera between flying and walking, e.g. `movemode walk`")
+                .optional_argument("mode", ArgumentKind::String)
+                .send_event(|command| {
+                    let mode = match command.argument(0) {
+                        None => None,
+                        Some(value) => Some(MovementMode::parse(&value.to_string())
+                            .ok_or_else(|| ArgumentError::WrongType {
+                                argument: "<mode>".to_string(),
+                                expected: "walk or fly",
+                                found: value.clone(),
+                            })?),
+                    };
+                    Ok(SetMovementMode(mode))
+                }))
+            .add_system_set(SystemSet::on_update(GameState::Playing)
+                .with_system(on_set_movement_mode.system())
+                .with_system(read_walk_inputs.system().label("read_walk_inputs"))
+                .with_system(detect_ground.system().label("detect_ground"))
+                .with_system(crouch.system().after("read_walk_inputs").after("detect_ground"))
+                .with_system(walk.system().after("read_walk_inputs").after("detect_ground")));
+    }
+}
+
+
+/// How the Possessed camera moves
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MovementMode {
+    /// Spectator Camera: flies through everything
+    Fly,
+    /// Character: a physics body, which walks on the ground
+    Walk,
+}
+
+impl MovementMode {
+    pub fn parse(name: &str) -> Option<MovementMode> {
+        match name.to_lowercase().as_str() {
+            "fly" => Some(MovementMode::Fly),
+            "walk" => Some(MovementMode::Walk),
+            _default => None,
+        }
+    }
+}
+
+/// Event: switch to a MovementMode, or toggle it when None
+pub struct SetMovementMode(pub Option<MovementMode>);
+
+
+/// Walking configuration, driven by cvars
+#[derive(Debug, Clone)]
+pub struct CharacterSettings {
+    /// Units per second
+    pub speed: f32,
+    /// Units per second squared, towards the speed of the inputs
+    pub acceleration: f32,
+    /// Units per second squared, towards a standstill when on the ground without inputs
+    pub friction: f32,
+    /// Share of the acceleration available while airborne
+    pub air_control: f32,
+    /// Units per second, upwards
+    pub jump_speed: f32,
+    /// Degrees from the horizontal
+    pub max_slope: f32,
+    pub crouch_speed_factor: f32,
+}
+
+impl Default for CharacterSettings {
+    fn default() -> Self {
+        CharacterSettings {
+            speed: 6.0,
+            acceleration: 40.0,
+            friction: 30.0,
+            air_control: 0.3,
+            jump_speed: 5.0,
+            max_slope: 45.0,
+            crouch_speed_factor: 0.5,
+        }
+    }
+}
+
+
+/// A walking body. Its child with a CharacterEye is the camera.
+#[derive(Debug, Clone, Default)]
+pub struct CharacterController {
+    /// Direction of the inputs of this frame, relative to the body: x to the right, z backwards
+    pub wish_direction: Vec3,
+    pub wants_jump: bool,
+    pub wants_crouch: bool,
+    pub is_crouching: bool,
+    /// On ground that is flat enough to walk on
+    pub is_grounded: bool,
+    pub ground_normal: Vec3,
+}
+
+/// The camera of a CharacterController, pitched up and down independently of its body
+pub struct CharacterEye;
+
+
+/// Keep the settings in sync with the cvars, as soon as they are set from the console
+fn apply_cvars(cvars: Res<Cvars>, mut settings: ResMut<CharacterSettings>) {
+    if !cvars.is_changed() {
+        return;
+    }
+
+    settings.speed = cvars.number("walk_speed").unwrap_or(settings.speed);
+    settings.acceleration = cvars.number("walk_acceleration").unwrap_or(settings.acceleration);
+    settings.friction = cvars.number("walk_friction").unwrap_or(settings.friction);
+    settings.air_control = cvars.number("walk_air_control").unwrap_or(settings.air_control);
+    settings.jump_speed = cvars.number("jump_speed").unwrap_or(settings.jump_speed);
+    settings.max_slope = cvars.number("max_slope").unwrap_or(settings.max_slope);
+    settings.crouch_speed_factor = cvars.number("crouch_speed_factor").unwrap_or(settings.crouch_speed_factor);
+}
+
+/// Turn the Possessed Spectator Camera into the eye of a new character, or turn a character back into a camera
+fn on_set_movement_mode(
+    mut commands: Commands,
+    mut set_movement_mode: EventReader<SetMovementMode>,
+    flying: Query<(Entity, &Transform), (With<Possessed>, With<SpectatorCamera>)>,
+    walking: Query<(Entity, &Transform, &Children), (With<Possessed>, With<CharacterController>)>,
+    eyes: Query<&Transform, With<CharacterEye>>,
+    mut log_to_console: EventWriter<LogToConsole>,
+) {
+    set_movement_mode.iter().for_each(|event| {
+        let current = if walking.iter().next().is_some() { MovementMode::Walk } else { MovementMode::Fly };
+        let mode = event.0.unwrap_or(match current {
+            MovementMode::Fly => MovementMode::Walk,
+            MovementMode::Walk => MovementMode::Fly,
+        });
+        if mode == current {
+            log_to_console.send(LogToConsole(format!("Already in {:?} mode", mode)));
+            return;
+        }
+
+        match mode {
+            MovementMode::Walk => flying.iter().for_each(|(camera, transform)| {
+                let (yaw, pitch) = yaw_and_pitch(transform.rotation);
+                let body = commands
+                    .spawn_bundle((
+                        Transform {
+                            translation: transform.translation - Vec3::Y * STANDING_EYE_HEIGHT,
+                            rotation: Quat::from_rotation_y(yaw),
+                            ..Default::default()
+                        },
+                        GlobalTransform::identity(),
+                    ))
+                    .insert(RigidBody::Dynamic)
+                    .insert(capsule(STANDING_HALF_SEGMENT).collision_shape(Vec3::ONE))
+                    .insert(Velocity::default())
+                    // The inputs turn the body, not the collisions; and only this controller slows it down
+                    .insert(RotationConstraints::lock())
+                    .insert(PhysicMaterial { friction: 0.0, restitution: 0.0, ..Default::default() })
+                    .insert(CollisionGroup::new("Player"))
+                    .insert(CharacterController::default())
+                    .insert(Possessed)
+                    .id();
+
+                commands.entity(camera)
+                    .remove::<Possessed>()
+                    .insert(CharacterEye)
+                    .insert(Transform {
+                        translation: Vec3::Y * STANDING_EYE_HEIGHT,
+                        rotation: Quat::from_rotation_x(pitch),
+                        ..Default::default()
+                    });
+                commands.entity(body).push_children(&[camera]);
+                log_to_console.send(LogToConsole("Walking".to_string()));
+            }),
+            MovementMode::Fly => walking.iter().for_each(|(body, transform, children)| {
+                children.iter()
+                    .filter_map(|&it| eyes.get(it).ok().map(|eye| (it, eye)))
+                    .for_each(|(camera, eye)| {
+                        commands.entity(camera)
+                            .remove::<Parent>()
+                            .remove::<CharacterEye>()
+                            .insert(Possessed)
+                            .insert(Transform {
+                                translation: transform.translation + transform.rotation.mul_vec3(eye.translation),
+                                rotation: transform.rotation * eye.rotation,
+                                ..Default::default()
+                            });
+                    });
+                // Not recursively: the camera lives on
+                commands.entity(body).despawn();
+                log_to_console.send(LogToConsole("Flying".to_string()));
+            }),
+        }
+    });
+}
+
+/// Gather the inputs of this frame, which arrive as one event per axis or held button
+fn read_walk_inputs(
+    mut characters: Query<&mut CharacterController, With<Possessed>>,
+    mut move_forward: EventReader<MoveForward>,
+    mut strafe_right: EventReader<StrafeRight>,
+    mut jump: EventReader<Jump>,
+    mut crouch: EventReader<Crouch>,
+) {
+    let forward: f32 = move_forward.iter().map(|it| it.0).sum();
+    let right: f32 = strafe_right.iter().map(|it| it.0).sum();
+    let wants_jump = jump.iter().any(|it| it.0);
+    let wants_crouch = crouch.iter().any(|it| it.0);
+
+    characters.iter_mut().for_each(|mut controller| {
+        let wish_direction = Vec3::new(right, 0.0, -forward);
+        controller.wish_direction = if wish_direction.length() > 1.0 { wish_direction.normalize() } else { wish_direction };
+        controller.wants_jump = wants_jump;
+        controller.wants_crouch = wants_crouch;
+    });
+}
+
+/// Cast a ray below each character, to find whether it stands on walkable ground: triggers aren't ground
+fn detect_ground(
+    physics_world: PhysicsWorld,
+    settings: Res<CharacterSettings>,
+    triggers: Query<(), With<Trigger>>,
+    mut characters: Query<(Entity, &Transform, &CollisionShape, &mut CharacterController)>,
+) {
+    characters.iter_mut().for_each(|(entity, transform, shape, mut controller)| {
+        let half_height = capsule_half_height(shape);
+        let hit = physics_world.ray_cast_with_filter(
+            transform.translation,
+            -Vec3::Y * (half_height + GROUND_TOLERANCE),
+            true,
+            CollisionLayers::default(),
+            |it| it != entity && triggers.get(it).is_err(),
+        );
+
+        let normal = hit.map(|it| it.normal);
+        controller.ground_normal = normal.unwrap_or(Vec3::Y);
+        controller.is_grounded = normal.map_or(false, |it| is_walkable(it, settings.max_slope));
+    });
+}
+
+/// Shrink the capsule while crouching, and grow it back once there is room to stand up
+fn crouch(
+    physics_world: PhysicsWorld,
+    triggers: Query<(), With<Trigger>>,
+    mut characters: Query<(Entity, &mut Transform, &mut CollisionShape, &mut CharacterController, Option<&Children>)>,
+    mut eyes: Query<&mut Transform, (With<CharacterEye>, Without<CharacterController>)>,
+) {
+    characters.iter_mut().for_each(|(entity, mut transform, mut shape, mut controller, children)| {
+        if controller.wants_crouch == controller.is_crouching {
+            return;
+        }
+
+        let height_change = 2.0 * (STANDING_HALF_SEGMENT - CROUCHING_HALF_SEGMENT);
+        if !controller.wants_crouch {
+            // Only stand up if nothing but triggers is above the head
+            let is_blocked = physics_world.ray_cast_with_filter(
+                transform.translation,
+                Vec3::Y * (capsule_half_height(&shape) + height_change),
+                true,
+                CollisionLayers::default(),
+                |it| it != entity && triggers.get(it).is_err(),
+            ).is_some();
+            if is_blocked {
+                return;
+            }
+        }
+
+        controller.is_crouching = controller.wants_crouch;
+        let (half_segment, eye_height, center_offset) = match controller.is_crouching {
+            true => (CROUCHING_HALF_SEGMENT, CROUCHING_EYE_HEIGHT, -height_change / 2.0),
+            false => (STANDING_HALF_SEGMENT, STANDING_EYE_HEIGHT, height_change / 2.0),
+        };
+
+        // Keep the feet where they are
+        *shape = capsule(half_segment).collision_shape(Vec3::ONE);
+        transform.translation.y += center_offset;
+        children.into_iter()
+            .flat_map(|it| it.iter())
+            .for_each(|&it| if let Ok(mut eye) = eyes.get_mut(it) {
+                eye.translation = Vec3::Y * eye_height;
+            });
+    });
+}
+
+/// Accelerate towards the inputs, slow down on the ground without them, and jump
+fn walk(
+    settings: Res<CharacterSettings>,
+    frame_time: Res<FrameTime>,
+    mut characters: Query<(&Transform, &mut Velocity, &CharacterController)>,
+) {
+    let delta_seconds = frame_time.delta_seconds;
+    characters.iter_mut().for_each(|(transform, mut velocity, controller)| {
+        let speed_factor = if controller.is_crouching { settings.crouch_speed_factor } else { 1.0 };
+        let wish = transform.rotation.mul_vec3(controller.wish_direction) * speed_factor;
+
+        let mut linear = walk_velocity(velocity.linear, wish, controller.is_grounded, &settings, delta_seconds);
+        if controller.wants_jump && controller.is_grounded && !controller.is_crouching {
+            linear.y = settings.jump_speed;
+        }
+
+        if linear != velocity.linear {
+            velocity.linear = linear;
+        }
+    });
+}
+
+
+/// Helpers
+/// The new velocity: only its horizontal part changes, towards `wish` (a direction, scaled by how far
+///     the inputs are pushed) times the speed. Airborne without inputs, the momentum is kept.
+pub fn walk_velocity(velocity: Vec3, wish: Vec3, is_grounded: bool, settings: &CharacterSettings, delta_seconds: f32) -> Vec3 {
+    let horizontal = Vec3::new(velocity.x, 0.0, velocity.z);
+    let target = Vec3::new(wish.x, 0.0, wish.z) * settings.speed;
+    let has_inputs = target != Vec3::ZERO;
+
+    let rate = match (is_grounded, has_inputs) {
+        (true, true) => settings.acceleration,
+        (true, false) => settings.friction,
+        (false, true) => settings.acceleration * settings.air_control,
+        (false, false) => return velocity,
+    };
+
+    let horizontal = move_towards(horizontal, target, rate * delta_seconds);
+    Vec3::new(horizontal.x, velocity.y, horizontal.z)
+}
+
+/// Whether ground with this normal is flat enough to walk on
+pub fn is_walkable(normal: Vec3, max_slope: f32) -> bool {
+    normal != Vec3::ZERO && normal.angle_between(Vec3::Y).to_degrees() <= max_slope
+}
+
+/// Heading around the vertical axis, and pitch above the horizon, in radians
+fn yaw_and_pitch(rotation: Quat) -> (f32, f32) {
+    let forward = rotation.mul_vec3(-Vec3::Z);
+    ((-forward.x).atan2(-forward.z), forward.y.clamp(-1.0, 1.0).asin())
+}
+
+fn move_towards(current: Vec3, target: Vec3, max_delta: f32) -> Vec3 {
+    let delta = target - current;
+    let distance = delta.length();
+    if distance <= max_delta {
+        target
+    } else {
+        current + delta / distance * max_delta
+    }
+}
+
+/// The character's capsule, standing or crouching
+fn capsule(half_segment: f32) -> shape::Capsule {
+    shape::Capsule { radius: CAPSULE_RADIUS, depth: 2.0 * half_segment, ..Default::default() }
+}
+
+/// From the center of a capsule to its bottom
+fn capsule_half_height(shape: &CollisionShape) -> f32 {
+    match shape {
+        CollisionShape::Capsule { half_segment, radius } => half_segment + radius,
+        _default => STANDING_HALF_SEGMENT + CAPSULE_RADIUS,
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+
+    use super::{CharacterSettings, is_walkable, walk_velocity, yaw_and_pitch};
+
+    const DELTA_SECONDS: f32 = 1.0 / 60.0;
+
+    #[test]
+    fn walking_accelerates_up_to_the_speed() {
+        let settings = CharacterSettings::default();
+        let mut velocity = Vec3::ZERO;
+
+        velocity = walk_velocity(velocity, -Vec3::Z, true, &settings, DELTA_SECONDS);
+        assert!((velocity.z + settings.acceleration * DELTA_SECONDS).abs() < 1e-5);
+
+        (0..120).for_each(|_| velocity = walk_velocity(velocity, -Vec3::Z, true, &settings, DELTA_SECONDS));
+        assert!((velocity - Vec3::new(0.0, 0.0, -settings.speed)).length() < 1e-5);
+    }
+
+    #[test]
+    fn friction_stops_on_the_ground_but_not_in_the_air() {
+        let settings = CharacterSettings::default();
+        let falling = Vec3::new(settings.speed, -3.0, 0.0);
+
+        assert_eq!(walk_velocity(falling, Vec3::ZERO, false, &settings, DELTA_SECONDS), falling);
+
+        let mut velocity = falling;
+        (0..60).for_each(|_| velocity = walk_velocity(velocity, Vec3::ZERO, true, &settings, DELTA_SECONDS));
+        assert_eq!(velocity, Vec3::new(0.0, -3.0, 0.0));
+    }
+
+    #[test]
+    fn steep_slopes_are_not_walkable() {
+        assert!(is_walkable(Vec3::Y, 45.0));
+        assert!(is_walkable(Vec3::new(0.0, 1.0, 0.5).normalize(), 45.0));
+        assert!(!is_walkable(Vec3::new(0.0, 1.0, 2.0).normalize(), 45.0));
+        assert!(!is_walkable(Vec3::X, 45.0));
+    }
+
+    #[test]
+    fn rotation_is_split_into_yaw_and_pitch() {
+        let rotation = Quat::from_rotation_y(0.5) * Quat::from_rotation_x(-0.3);
+        let (yaw, pitch) = yaw_and_pitch(rotation);
+
+        let rebuilt = Quat::from_rotation_y(yaw) * Quat::from_rotation_x(pitch);
+        assert!((rebuilt.mul_vec3(-Vec3::Z) - rotation.mul_vec3(-Vec3::Z)).length() < 1e-5);
+        assert!((pitch + 0.3).abs() < 1e-5);
+    }
+}
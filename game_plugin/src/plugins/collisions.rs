@@ -0,0 +1,370 @@
+/// Gameplay layer over Heron's collisions: trigger volumes sending typed `EnteredTrigger`/`ExitedTrigger`
+///     events (optionally filtered by a marker component, e.g. `EnteredTrigger<CubeActor>`), and collision
+///     groups declared by name in `assets/collision_layers.yaml`.
+use std::marker::PhantomData;
+
+use bevy::prelude::*;
+use bevy::ecs::component::Component;
+use heron::prelude::*;
+use serde::Deserialize;
+
+use crate::plugins::console::commands::{AddConsoleCommand, ArgumentKind, CommandResult, ConsoleCommand};
+use crate::plugins::console::parser::{CommandLine, Value as ConsoleValue};
+use crate::plugins::player::{CubeActor, Possessed};
+use crate::utils::colliders::ToCollisionShape;
+use crate::utils::config;
+
+
+/// Within the assets directory
+pub const COLLISION_LAYERS_FILE: &str = "collision_layers.yaml";
+
+/// Used when the file is missing or invalid: everything collides with everything, but triggers only detect bodies
+const DEFAULT_COLLISION_LAYERS: &str = "
+- name: World
+  collides_with: [World, Actor, Player]
+- name: Actor
+  collides_with: [World, Actor, Player, Trigger]
+- name: Player
+  collides_with: [World, Actor, Trigger]
+- name: Trigger
+  collides_with: [Actor, Player]
+";
+
+/// Heron's CollisionLayers are 32 bits wide
+const MAX_LAYERS: usize = 32;
+
+
+/// Plugins
+pub struct CollisionsPlugin;
+
+impl Plugin for CollisionsPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app
+            .insert_resource(CollisionLayersConfig::load())
+            .add_event::<EnteredTrigger>()
+            .add_event::<ExitedTrigger>()
+            .add_trigger_filter::<CubeActor>()
+            .add_trigger_filter::<Possessed>()
+            .add_console_command(ConsoleCommand::new("trigger",
+                "Spawn a trigger volume, logging what enters and exits it, e.g. `trigger 0,1,0 2,1,2`")
+                .argument("center", ArgumentKind::Vector)
+                .argument("half_extents", ArgumentKind::Vector)
+                .run(trigger_command))
+            .add_system(apply_collision_groups.system())
+            .add_system(detect_triggers.system().label(TriggerSystem::Detect))
+            .add_system(log_triggers.system().after(TriggerSystem::Detect));
+    }
+}
+
+#[derive(SystemLabel, Debug, Clone, PartialEq, Eq, Hash)]
+pub enum TriggerSystem {
+    Detect,
+}
+
+
+/// A trigger volume: a sensor, whose overlaps are sent as EnteredTrigger and ExitedTrigger events
+pub struct Trigger;
+
+/// Event: `other` started overlapping `trigger`. `T` is the marker component `other` must have, if any.
+pub struct EnteredTrigger<T = ()> {
+    pub trigger: Entity,
+    pub other: Entity,
+    marker: PhantomData<T>,
+}
+
+/// Event: `other` stopped overlapping `trigger`
+pub struct ExitedTrigger<T = ()> {
+    pub trigger: Entity,
+    pub other: Entity,
+    marker: PhantomData<T>,
+}
+
+impl<T> EnteredTrigger<T> {
+    pub fn new(trigger: Entity, other: Entity) -> Self {
+        EnteredTrigger { trigger, other, marker: PhantomData }
+    }
+}
+
+impl<T> ExitedTrigger<T> {
+    pub fn new(trigger: Entity, other: Entity) -> Self {
+        ExitedTrigger { trigger, other, marker: PhantomData }
+    }
+}
+
+/// A trigger volume's bundle: a sensor box, in the Trigger collision group
+#[derive(Bundle)]
+pub struct TriggerBundle {
+    pub transform: Transform,
+    pub global_transform: GlobalTransform,
+    pub body: RigidBody,
+    pub shape: CollisionShape,
+    pub group: CollisionGroup,
+    pub trigger: Trigger,
+}
+
+impl TriggerBundle {
+    pub fn new(center: Vec3, half_extents: Vec3) -> TriggerBundle {
+        TriggerBundle {
+            transform: Transform::from_translation(center),
+            global_transform: GlobalTransform::from_translation(center),
+            body: RigidBody::Sensor,
+            shape: shape::Box::new(half_extents.x * 2.0, half_extents.y * 2.0, half_extents.z * 2.0)
+                .collision_shape(Vec3::ONE),
+            group: CollisionGroup::new("Trigger"),
+            trigger: Trigger,
+        }
+    }
+}
+
+
+/// Lets any plugin receive the trigger events of the entities with a marker component
+pub trait AddTriggerFilter {
+    fn add_trigger_filter<T: Component>(&mut self) -> &mut Self;
+}
+
+impl AddTriggerFilter for AppBuilder {
+    fn add_trigger_filter<T: Component>(&mut self) -> &mut Self {
+        self
+            .add_event::<EnteredTrigger<T>>()
+            .add_event::<ExitedTrigger<T>>()
+            .add_system(filter_trigger_events::<T>.system().after(TriggerSystem::Detect))
+    }
+}
+
+
+/// The collision group of an entity, by name: its CollisionLayers are looked up in the CollisionLayersConfig
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CollisionGroup(pub String);
+
+impl CollisionGroup {
+    pub fn new(name: &str) -> CollisionGroup {
+        CollisionGroup(name.to_string())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+struct LayerConfig {
+    name: String,
+    #[serde(default)]
+    collides_with: Vec<String>,
+}
+
+/// Collision groups, by name, and the groups each of them collides with
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CollisionLayersConfig {
+    names: Vec<String>,
+    masks: Vec<u32>,
+}
+
+impl Default for CollisionLayersConfig {
+    fn default() -> Self {
+        CollisionLayersConfig::parse(DEFAULT_COLLISION_LAYERS).expect("The default collision layers are valid")
+    }
+}
+
+impl CollisionLayersConfig {
+    /// Read `assets/collision_layers.yaml`, falling back to the defaults if it is missing or invalid
+    pub fn load() -> CollisionLayersConfig {
+        let path = match config::get_assets_dir().map(|it| it.join(COLLISION_LAYERS_FILE)) {
+            Some(path) if path.exists() => path,
+            _default => return CollisionLayersConfig::default(),
+        };
+
+        match std::fs::read_to_string(&path).map_err(|error| error.to_string())
+            .and_then(|contents| CollisionLayersConfig::parse(&contents))
+        {
+            Ok(config) => {
+                info!("Loaded {} collision groups from '{}'", config.names.len(), path.display());
+                config
+            },
+            Err(error) => {
+                error!("{}: {}", path.display(), error);
+                CollisionLayersConfig::default()
+            },
+        }
+    }
+
+    pub fn parse(contents: &str) -> Result<CollisionLayersConfig, String> {
+        let layers: Vec<LayerConfig> = serde_yaml::from_str(contents)
+            .map_err(|error| format!("Invalid collision layers: {}", error))?;
+        if layers.len() > MAX_LAYERS {
+            return Err(format!("At most {} collision groups are supported, found {}", MAX_LAYERS, layers.len()));
+        }
+
+        let names: Vec<String> = layers.iter().map(|it| it.name.clone()).collect();
+        let bit = |name: &String| names.iter()
+            .position(|it| it == name)
+            .map(|index| 1u32 << index)
+            .ok_or_else(|| format!("Unknown collision group '{}'", name));
+
+        let masks = layers.iter()
+            .map(|layer| layer.collides_with.iter().try_fold(0u32, |mask, it| Ok::<u32, String>(mask | bit(it)?)))
+            .collect::<Result<Vec<u32>, String>>()?;
+        Ok(CollisionLayersConfig { names, masks })
+    }
+
+    /// Heron's CollisionLayers for a group: in the group, and colliding with its `collides_with`
+    pub fn layers(&self, name: &str) -> Option<CollisionLayers> {
+        self.names.iter()
+            .position(|it| it.eq_ignore_ascii_case(name))
+            .map(|index| CollisionLayers::from_bits(1u32 << index, self.masks[index]))
+    }
+}
+
+
+/// Systems
+/// Give the entities of a CollisionGroup their CollisionLayers
+fn apply_collision_groups(
+    mut commands: Commands,
+    config: Res<CollisionLayersConfig>,
+    groups: Query<(Entity, &CollisionGroup), Changed<CollisionGroup>>,
+) {
+    groups.iter().for_each(|(entity, group)| match config.layers(&group.0) {
+        Some(layers) => {
+            commands.entity(entity).insert(layers);
+        },
+        None => warn!("Unknown collision group '{}' for {:?}", group.0, entity),
+    });
+}
+
+/// Turn Heron's collision events involving a Trigger into EnteredTrigger and ExitedTrigger events
+pub fn detect_triggers(
+    mut collision_events: EventReader<CollisionEvent>,
+    triggers: Query<(), With<Trigger>>,
+    mut entered_trigger: EventWriter<EnteredTrigger>,
+    mut exited_trigger: EventWriter<ExitedTrigger>,
+) {
+    collision_events.iter().for_each(|event| {
+        let (first, second) = event.rigid_body_entities();
+        let is_started = matches!(event, CollisionEvent::Started(..));
+
+        // Both of them may be triggers
+        [(first, second), (second, first)].iter()
+            .filter(|(trigger, _)| triggers.get(*trigger).is_ok())
+            .for_each(|&(trigger, other)| match is_started {
+                true => entered_trigger.send(EnteredTrigger::new(trigger, other)),
+                false => exited_trigger.send(ExitedTrigger::new(trigger, other)),
+            });
+    });
+}
+
+/// Forward the trigger events of the entities with a `T` marker
+fn filter_trigger_events<T: Component>(
+    mut entered_trigger: EventReader<EnteredTrigger>,
+    mut exited_trigger: EventReader<ExitedTrigger>,
+    markers: Query<(), With<T>>,
+    mut entered_filtered: EventWriter<EnteredTrigger<T>>,
+    mut exited_filtered: EventWriter<ExitedTrigger<T>>,
+) {
+    entered_trigger.iter()
+        .filter(|it| markers.get(it.other).is_ok())
+        .for_each(|it| entered_filtered.send(EnteredTrigger::new(it.trigger, it.other)));
+    exited_trigger.iter()
+        .filter(|it| markers.get(it.other).is_ok())
+        .for_each(|it| exited_filtered.send(ExitedTrigger::new(it.trigger, it.other)));
+}
+
+/// Log every trigger event, which `loglevel debug` shows in the console
+fn log_triggers(mut entered_trigger: EventReader<EnteredTrigger>, mut exited_trigger: EventReader<ExitedTrigger>) {
+    entered_trigger.iter()
+        .for_each(|it| debug!("{} entered trigger {}", ConsoleValue::Entity(it.other), ConsoleValue::Entity(it.trigger)));
+    exited_trigger.iter()
+        .for_each(|it| debug!("{} exited trigger {}", ConsoleValue::Entity(it.other), ConsoleValue::Entity(it.trigger)));
+}
+
+
+/// Commands
+/// `trigger <center> <half extents>`: remove it with `despawn`
+fn trigger_command(world: &mut World, command: &CommandLine) -> CommandResult {
+    let center = command.require(0, "a vector", ConsoleValue::as_vec3)?;
+    let half_extents = command.require(1, "a vector", ConsoleValue::as_vec3)?;
+
+    let trigger = world.spawn()
+        .insert_bundle(TriggerBundle::new(center, half_extents.abs()))
+        .id();
+    Ok(format!("Spawned trigger {}", ConsoleValue::Entity(trigger)))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use bevy::app::Events;
+    use bevy::prelude::*;
+    use heron::prelude::*;
+    use heron::PhysicsSteps;
+    use heron::rapier_plugin::RapierPlugin;
+
+    use crate::plugins::player::{CubeActor, floor_shape};
+    use crate::utils::colliders::ToCollisionShape;
+    use super::{
+        AddTriggerFilter, CollisionGroup, CollisionLayersConfig, EnteredTrigger, ExitedTrigger, TriggerBundle, TriggerSystem
+    };
+    use super::{apply_collision_groups, detect_triggers};
+
+    #[test]
+    fn layers_are_read_by_name() {
+        let config = CollisionLayersConfig::parse("
+- name: World
+  collides_with: [Actor]
+- name: Actor
+  collides_with: [World, Actor]
+").unwrap();
+
+        assert_eq!(config.layers("world"), Some(CollisionLayers::from_bits(0b01, 0b10)));
+        assert_eq!(config.layers("Actor"), Some(CollisionLayers::from_bits(0b10, 0b11)));
+        assert_eq!(config.layers("Trigger"), None);
+
+        assert!(CollisionLayersConfig::parse("- name: World\n  collides_with: [Nope]").is_err());
+        assert!(CollisionLayersConfig::default().layers("Trigger").is_some());
+    }
+
+    /// Drop a cube onto the heightfield floor, into a trigger lying on it
+    #[test]
+    fn falling_cube_enters_a_trigger() {
+        let mut app = App::build();
+        app
+            .add_plugins(MinimalPlugins)
+            .add_plugin(bevy::transform::TransformPlugin)
+            // PhysicsPlugin would add heron's debug renderer, which needs a GPU
+            .add_plugin(RapierPlugin)
+            .insert_resource(PhysicsSteps::every_frame(std::time::Duration::from_secs_f64(1.0 / 60.0)))
+            .insert_resource(Gravity::from(Vec3::new(0.0, -9.81, 0.0)))
+            .init_resource::<CollisionLayersConfig>()
+            .add_event::<EnteredTrigger>()
+            .add_event::<ExitedTrigger>()
+            .add_trigger_filter::<CubeActor>()
+            .add_system(apply_collision_groups.system())
+            .add_system(detect_triggers.system().label(TriggerSystem::Detect));
+        let mut app = app.app;
+
+        app.world.spawn()
+            .insert_bundle((Transform::identity(), GlobalTransform::identity()))
+            .insert(RigidBody::Static)
+            .insert(floor_shape())
+            .insert(CollisionGroup::new("World"));
+        let trigger = app.world.spawn()
+            .insert_bundle(TriggerBundle::new(Vec3::new(0.0, 1.0, 0.0), Vec3::new(2.0, 1.0, 2.0)))
+            .id();
+        let cube = app.world.spawn()
+            .insert_bundle((Transform::from_xyz(0.0, 8.0, 0.0), GlobalTransform::from_xyz(0.0, 8.0, 0.0)))
+            .insert(RigidBody::Dynamic)
+            .insert(shape::Cube::default().collision_shape(Vec3::ONE))
+            .insert(CollisionGroup::new("Actor"))
+            .insert(CubeActor)
+            .id();
+
+        let mut entered = Vec::new();
+        for _ in 0..300 {
+            app.update();
+            let events = app.world.get_resource::<Events<EnteredTrigger<CubeActor>>>().unwrap();
+            entered.extend(events.get_reader().iter(events).map(|it| (it.trigger, it.other)));
+            if !entered.is_empty() {
+                break;
+            }
+        }
+
+        assert_eq!(entered.first(), Some(&(trigger, cube)));
+        let height = app.world.get::<Transform>(cube).unwrap().translation.y;
+        assert!(height < 8.0, "the cube did not fall: {}", height);
+    }
+}
@@ -0,0 +1,408 @@
+/// Console command registry: any plugin can register its own commands, with `app.add_console_command(...)`.
+use std::fmt;
+use std::sync::Arc;
+
+use bevy::prelude::*;
+use bevy::app::Events;
+use bevy::ecs::component::Component;
+
+use crate::plugins::actions::GameActionBinding;
+use crate::plugins::console::parser::{ArgumentError, CommandLine, Value, parse_command_line};
+
+
+/// Output of a command: a message for the console (possibly empty), or an error
+pub type CommandResult = Result<String, CommandError>;
+
+type CommandHandler = Arc<dyn Fn(&mut World, &CommandLine) -> CommandResult + Send + Sync>;
+
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CommandError {
+    Arguments(ArgumentError),
+    Failed(String),
+}
+
+impl fmt::Display for CommandError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CommandError::Arguments(error) => write!(f, "{}", error),
+            CommandError::Failed(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for CommandError {}
+
+impl From<ArgumentError> for CommandError {
+    fn from(error: ArgumentError) -> Self {
+        CommandError::Arguments(error)
+    }
+}
+
+impl From<String> for CommandError {
+    fn from(message: String) -> Self {
+        CommandError::Failed(message)
+    }
+}
+
+
+/// Expected type of an argument, checked before the command runs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArgumentKind {
+    Integer,
+    Number,
+    Vector,
+    /// A single vector (`0,10,0`), or three numbers (`0 10 0`)
+    Position,
+    Entity,
+    Color,
+    /// Any value, read as text
+    String,
+    /// The name of a console command
+    Command,
+    /// The name of a cvar
+    Cvar,
+    /// The name of a `GameActionBinding`, e.g. `MoveForward`
+    Binding,
+}
+
+impl ArgumentKind {
+    pub fn describe(&self) -> &'static str {
+        match self {
+            ArgumentKind::Integer => "an integer",
+            ArgumentKind::Number => "a number",
+            ArgumentKind::Vector => "a vector",
+            ArgumentKind::Position => "a vector or 3 numbers",
+            ArgumentKind::Entity => "an entity id",
+            ArgumentKind::Color => "a color name or hex code",
+            ArgumentKind::String => "text",
+            ArgumentKind::Command => "a command name",
+            ArgumentKind::Cvar => "a cvar name",
+            ArgumentKind::Binding => "an action name",
+        }
+    }
+
+    fn accepts(&self, value: &Value) -> bool {
+        match self {
+            ArgumentKind::Integer => value.as_i64().is_some(),
+            ArgumentKind::Number => value.as_f32().is_some(),
+            ArgumentKind::Vector => matches!(value, Value::Vector(_)),
+            ArgumentKind::Position => value.as_vec3().is_some() || value.as_f32().is_some(),
+            ArgumentKind::Entity => value.as_entity().is_some(),
+            ArgumentKind::Color => value.as_color().is_some(),
+            ArgumentKind::Binding => GameActionBinding::parse(&value.to_string()).is_some(),
+            // Names are looked up by the commands themselves, which know what is registered
+            ArgumentKind::String | ArgumentKind::Command | ArgumentKind::Cvar => true,
+        }
+    }
+
+    /// Check the positional argument(s) at `index`, returning how many of them it takes
+    fn check(&self, command: &CommandLine, index: usize, name: &str) -> Result<usize, ArgumentError> {
+        if *self == ArgumentKind::Position {
+            return match command.argument(index).and_then(Value::as_vec3) {
+                Some(_) => Ok(1),
+                None => command.vec3_at(index).map(|_| 3),
+            };
+        }
+
+        let value = command.argument(index)
+            .ok_or(ArgumentError::Missing { index, expected: self.describe() })?;
+        if self.accepts(value) {
+            Ok(1)
+        } else {
+            Err(ArgumentError::WrongType {
+                argument: format!("<{}>", name),
+                expected: self.describe(),
+                found: value.clone(),
+            })
+        }
+    }
+}
+
+
+#[derive(Debug, Clone)]
+pub struct ArgumentSpec {
+    pub name: &'static str,
+    pub kind: ArgumentKind,
+    pub is_optional: bool,
+    /// Given as `name=value`, rather than by position
+    pub is_named: bool,
+    /// Takes every remaining positional argument
+    pub is_variadic: bool,
+}
+
+impl fmt::Display for ArgumentSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let argument = match (self.is_named, self.is_variadic) {
+            (true, _) => format!("{}=<{}>", self.name, self.name),
+            (false, true) => format!("<{}...>", self.name),
+            (false, false) => format!("<{}>", self.name),
+        };
+
+        if self.is_optional {
+            write!(f, "[{}]", argument)
+        } else {
+            write!(f, "{}", argument)
+        }
+    }
+}
+
+
+/// A console command: its name, aliases, arguments, help text and handler
+#[derive(Clone)]
+pub struct ConsoleCommand {
+    pub name: &'static str,
+    pub aliases: Vec<&'static str>,
+    pub arguments: Vec<ArgumentSpec>,
+    pub help: &'static str,
+    handler: CommandHandler,
+}
+
+impl ConsoleCommand {
+    pub fn new(name: &'static str, help: &'static str) -> ConsoleCommand {
+        ConsoleCommand {
+            name,
+            aliases: Vec::new(),
+            arguments: Vec::new(),
+            help,
+            handler: Arc::new(|_, command| Err(format!("'{}' does nothing yet", command.name).into())),
+        }
+    }
+
+    pub fn alias(mut self, alias: &'static str) -> Self {
+        self.aliases.push(alias);
+        self
+    }
+
+    pub fn argument(self, name: &'static str, kind: ArgumentKind) -> Self {
+        self.with_argument(ArgumentSpec { name, kind, is_optional: false, is_named: false, is_variadic: false })
+    }
+
+    pub fn optional_argument(self, name: &'static str, kind: ArgumentKind) -> Self {
+        self.with_argument(ArgumentSpec { name, kind, is_optional: true, is_named: false, is_variadic: false })
+    }
+
+    pub fn variadic_argument(self, name: &'static str, kind: ArgumentKind) -> Self {
+        self.with_argument(ArgumentSpec { name, kind, is_optional: true, is_named: false, is_variadic: true })
+    }
+
+    /// An optional `name=value` argument
+    pub fn named_argument(self, name: &'static str, kind: ArgumentKind) -> Self {
+        self.with_argument(ArgumentSpec { name, kind, is_optional: true, is_named: true, is_variadic: false })
+    }
+
+    fn with_argument(mut self, argument: ArgumentSpec) -> Self {
+        self.arguments.push(argument);
+        self
+    }
+
+    /// Run the command with exclusive access to the World
+    pub fn run(
+        mut self,
+        handler: impl Fn(&mut World, &CommandLine) -> CommandResult + Send + Sync + 'static,
+    ) -> Self {
+        self.handler = Arc::new(handler);
+        self
+    }
+
+    /// Run the command by sending an event, built from its arguments
+    pub fn send_event<E: Component>(
+        self,
+        to_event: impl Fn(&CommandLine) -> Result<E, ArgumentError> + Send + Sync + 'static,
+    ) -> Self {
+        self.run(move |world, command| {
+            let event = to_event(command)?;
+            send_event(world, event)?;
+            Ok(String::new())
+        })
+    }
+
+    pub fn is_named(&self, name: &str) -> bool {
+        self.name == name || self.aliases.iter().any(|&it| it == name)
+    }
+
+    /// e.g. `spawn <kind> [<position>] [color=<color>]`
+    pub fn usage(&self) -> String {
+        std::iter::once(self.name.to_string())
+            .chain(self.arguments.iter().map(ArgumentSpec::to_string))
+            .collect::<Vec<String>>()
+            .join(" ")
+    }
+
+    /// Check the arguments against the command's specs, so that handlers can rely on them
+    pub fn validate(&self, command: &CommandLine) -> Result<(), ArgumentError> {
+        let mut index = 0;
+        for spec in self.arguments.iter().filter(|it| !it.is_named) {
+            if spec.is_variadic {
+                while index < command.arguments.len() {
+                    index += spec.kind.check(command, index, spec.name)?;
+                }
+            } else if index < command.arguments.len() || !spec.is_optional {
+                index += spec.kind.check(command, index, spec.name)?;
+            }
+        }
+        if index < command.arguments.len() {
+            return Err(ArgumentError::TooMany { expected: index, found: command.arguments.len() });
+        }
+
+        command.named_arguments.iter().try_for_each(|(name, value)| {
+            let spec = self.arguments.iter()
+                .find(|it| it.is_named && it.name == name)
+                .ok_or_else(|| ArgumentError::UnknownName { name: name.clone() })?;
+            if spec.kind.accepts(value) {
+                Ok(())
+            } else {
+                Err(ArgumentError::WrongType {
+                    argument: name.clone(),
+                    expected: spec.kind.describe(),
+                    found: value.clone(),
+                })
+            }
+        })
+    }
+}
+
+
+/// Every registered console command
+#[derive(Default, Clone)]
+pub struct ConsoleCommands(Vec<ConsoleCommand>);
+
+impl ConsoleCommands {
+    /// Register a command, replacing any command with the same name
+    pub fn register(&mut self, command: ConsoleCommand) {
+        if let Some(existing) = self.0.iter_mut().find(|it| it.name == command.name) {
+            warn!("Console command '{}' was registered twice; keeping the last one", command.name);
+            *existing = command;
+        } else {
+            self.0.push(command);
+        }
+    }
+
+    pub fn find(&self, name: &str) -> Option<&ConsoleCommand> {
+        self.0.iter().find(|it| it.is_named(name))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &ConsoleCommand> {
+        self.0.iter()
+    }
+}
+
+
+/// Lets any plugin register console commands
+pub trait AddConsoleCommand {
+    fn add_console_command(&mut self, command: ConsoleCommand) -> &mut Self;
+}
+
+impl AddConsoleCommand for AppBuilder {
+    fn add_console_command(&mut self, command: ConsoleCommand) -> &mut Self {
+        self.world_mut()
+            .get_resource_or_insert_with(ConsoleCommands::default)
+            .register(command);
+        self
+    }
+}
+
+
+/// Parse and run a command line, returning the message to log (if any)
+pub fn execute_command_line(world: &mut World, line: &str) -> CommandResult {
+    let command = parse_command_line(line)
+        .map_err(|error| error.to_string())?;
+
+    let handler = {
+        let commands = world.get_resource::<ConsoleCommands>()
+            .ok_or_else(|| "No console commands are registered".to_string())?;
+        let console_command = commands.find(&command.name)
+            .ok_or_else(|| format!("Unknown command: '{}'. Type 'help' for a list of commands", command.name))?;
+
+        console_command.validate(&command)
+            .map_err(|error| format!("{}\nUsage: {}", error, console_command.usage()))?;
+        console_command.handler.clone()
+    };
+
+    handler(world, &command)
+}
+
+/// Helper for command handlers: send an event
+pub fn send_event<E: Component>(world: &mut World, event: E) -> Result<(), CommandError> {
+    world.get_resource_mut::<Events<E>>()
+        .map(|mut events| events.send(event))
+        .ok_or_else(|| CommandError::Failed(format!("{} events are not registered", std::any::type_name::<E>())))
+}
+
+
+/// `help [command]`: generated from the registry
+pub fn help(world: &mut World, command: &CommandLine) -> CommandResult {
+    let commands = world.get_resource::<ConsoleCommands>()
+        .ok_or_else(|| "No console commands are registered".to_string())?;
+
+    match command.argument(0) {
+        None => {
+            let mut lines: Vec<String> = commands.iter()
+                .map(|it| format!("{} - {}", it.name, it.help))
+                .collect();
+            lines.sort();
+            lines.insert(0, "Commands (type 'help <command>' for details):".to_string());
+            Ok(lines.join("\n"))
+        },
+        Some(name) => {
+            let name = name.to_string().to_lowercase();
+            let console_command = commands.find(&name)
+                .ok_or_else(|| format!("Unknown command: '{}'", name))?;
+
+            let mut lines = vec![
+                format!("Usage: {}", console_command.usage()),
+                console_command.help.to_string(),
+            ];
+            if !console_command.aliases.is_empty() {
+                lines.push(format!("Aliases: {}", console_command.aliases.join(", ")));
+            }
+            console_command.arguments.iter().for_each(|it| {
+                lines.push(format!("  {}: {}", it, it.kind.describe()));
+            });
+            Ok(lines.join("\n"))
+        },
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+
+    use crate::plugins::console::parser::parse_command_line;
+    use super::{ArgumentKind, ConsoleCommand};
+
+    fn spawn_command() -> ConsoleCommand {
+        ConsoleCommand::new("spawn", "Spawn an actor")
+            .argument("kind", ArgumentKind::String)
+            .optional_argument("position", ArgumentKind::Position)
+            .named_argument("color", ArgumentKind::Color)
+    }
+
+    #[test]
+    fn arguments_are_validated_against_the_specs() {
+        let spawn = spawn_command();
+        let validate = |line: &str| spawn.validate(&parse_command_line(line).unwrap());
+
+        assert!(validate("spawn cube").is_ok());
+        assert!(validate("spawn cube 0 10 0 color=red").is_ok());
+        assert!(validate("spawn cube 0,10,0").is_ok());
+        assert!(validate("spawn").is_err());
+        assert!(validate("spawn cube 0 10").is_err());
+        assert!(validate("spawn cube 0,10,0 1").is_err());
+        assert!(validate("spawn cube color=nope").is_err());
+        assert!(validate("spawn cube size=2").is_err());
+    }
+
+    #[test]
+    fn help_is_generated() {
+        let mut world = World::default();
+        let mut commands = super::ConsoleCommands::default();
+        commands.register(spawn_command().alias("s"));
+        world.insert_resource(commands);
+
+        let help = super::help(&mut world, &parse_command_line("help s").unwrap()).unwrap();
+        assert!(help.contains("Usage: spawn <kind> [<position>] [color=<color>]"));
+        assert!(help.contains("Aliases: s"));
+    }
+}
@@ -0,0 +1,203 @@
+/// Tab completion of the console's command line: command names, then their arguments,
+///     according to each argument's `ArgumentKind` (cvar names, action names, booleans, colors...).
+use crate::plugins::actions::GameActionBinding;
+use crate::plugins::console::commands::{ArgumentKind, ArgumentSpec, ConsoleCommand, ConsoleCommands};
+use crate::plugins::console::cvars::Cvars;
+
+
+/// Color names understood by `Value::as_color`
+const COLOR_NAMES: [&str; 12] = [
+    "black", "blue", "cyan", "gray", "green", "grey", "orange", "pink", "purple", "red", "white", "yellow",
+];
+
+
+/// Candidates shown in the console's popup, after a Tab that matched more than one of them
+#[derive(Debug, Default, Clone)]
+pub struct CompletionCandidates(pub Vec<String>);
+
+
+/// Result of completing the word before the caret
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Completion {
+    /// Char index where the completed word starts
+    pub start: usize,
+    /// Every candidate matching the word, sorted
+    pub candidates: Vec<String>,
+}
+
+impl Completion {
+    /// The text replacing the word: the single candidate (followed by a space, to type the next argument),
+    ///     or the prefix common to all candidates.
+    pub fn replacement(&self) -> Option<String> {
+        match self.candidates.as_slice() {
+            [] => None,
+            [candidate] if candidate.ends_with('=') => Some(candidate.clone()),
+            [candidate] => Some(format!("{} ", candidate)),
+            [first, rest @ ..] => Some(rest.iter().fold(first.clone(), |prefix, it| common_prefix(&prefix, it))),
+        }
+    }
+}
+
+
+/// Everything that can be completed
+pub struct CompletionSources<'a> {
+    pub commands: &'a ConsoleCommands,
+    pub cvars: Option<&'a Cvars>,
+}
+
+impl<'a> CompletionSources<'a> {
+    /// Complete the word ending at the end of `before_caret`
+    pub fn complete(&self, before_caret: &str) -> Completion {
+        let word_start = before_caret.char_indices()
+            .rev()
+            .find(|(_, it)| it.is_whitespace())
+            .map_or(0, |(index, it)| index + it.len_utf8());
+        let word = &before_caret[word_start..];
+        let previous_words: Vec<&str> = before_caret[..word_start].split_whitespace().collect();
+
+        let candidates = match previous_words.split_first() {
+            None => self.command_names(),
+            Some((name, arguments)) => match self.commands.find(&name.to_lowercase()) {
+                Some(command) => self.argument_candidates(command, arguments, word),
+                None => Vec::new(),
+            },
+        };
+
+        let word_lowercase = word.to_lowercase();
+        let mut candidates: Vec<String> = candidates.into_iter()
+            .filter(|it| it.to_lowercase().starts_with(&word_lowercase))
+            .collect();
+        candidates.sort();
+        candidates.dedup();
+
+        Completion { start: before_caret[..word_start].chars().count(), candidates }
+    }
+
+    fn command_names(&self) -> Vec<String> {
+        self.commands.iter()
+            .flat_map(|it| std::iter::once(it.name).chain(it.aliases.iter().copied()))
+            .map(str::to_string)
+            .collect()
+    }
+
+    /// Candidates for the positional argument after `arguments`, or for a `name=value` argument
+    fn argument_candidates(&self, command: &ConsoleCommand, arguments: &[&str], word: &str) -> Vec<String> {
+        if let Some((name, _)) = word.split_once('=') {
+            return command.arguments.iter()
+                .find(|it| it.is_named && it.name == name)
+                .map_or_else(Vec::new, |it| self.values(it.kind).into_iter()
+                    .map(|value| format!("{}={}", name, value))
+                    .collect());
+        }
+
+        let index = arguments.iter().filter(|it| !it.contains('=')).count();
+        let positional = positional_argument(command, index)
+            .map_or_else(Vec::new, |it| self.values(it.kind));
+        let named = command.arguments.iter()
+            .filter(|it| it.is_named)
+            .map(|it| format!("{}=", it.name));
+
+        positional.into_iter().chain(named).collect()
+    }
+
+    fn values(&self, kind: ArgumentKind) -> Vec<String> {
+        match kind {
+            ArgumentKind::Color => COLOR_NAMES.iter().map(|it| it.to_string()).collect(),
+            ArgumentKind::Command => self.command_names(),
+            ArgumentKind::Cvar => self.cvars
+                .map_or_else(Vec::new, |cvars| cvars.iter().map(|it| it.name.to_string()).collect()),
+            ArgumentKind::Binding => GameActionBinding::ALL.iter().map(|it| format!("{:?}", it)).collect(),
+            _default => Vec::new(),
+        }
+    }
+}
+
+
+/// Helpers
+/// The positional argument spec at `index`: variadic arguments take every remaining position
+fn positional_argument(command: &ConsoleCommand, index: usize) -> Option<&ArgumentSpec> {
+    let mut positional = command.arguments.iter().filter(|it| !it.is_named);
+    let mut last = None;
+    for _ in 0..=index {
+        match positional.next() {
+            Some(spec) => last = Some(spec),
+            None => return last.filter(|it| it.is_variadic),
+        }
+    }
+    last
+}
+
+fn common_prefix(left: &str, right: &str) -> String {
+    left.chars()
+        .zip(right.chars())
+        .take_while(|(left, right)| left.eq_ignore_ascii_case(right))
+        .map(|(left, _)| left)
+        .collect()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+
+    use crate::plugins::console::commands::{ArgumentKind, ConsoleCommand, ConsoleCommands};
+    use crate::plugins::console::cvars::{Cvar, CvarValue, Cvars};
+    use super::CompletionSources;
+
+    fn create_sources() -> (ConsoleCommands, Cvars) {
+        let mut commands = ConsoleCommands::default();
+        commands.register(ConsoleCommand::new("spawn", "Spawn an actor")
+            .argument("kind", ArgumentKind::String)
+            .optional_argument("position", ArgumentKind::Position)
+            .named_argument("color", ArgumentKind::Color));
+        commands.register(ConsoleCommand::new("set", "Set a cvar")
+            .argument("cvar", ArgumentKind::Cvar)
+            .variadic_argument("value", ArgumentKind::String));
+        commands.register(ConsoleCommand::new("spectator", "Spawn a camera"));
+        commands.register(ConsoleCommand::new("bindings", "List bindings")
+            .optional_argument("action", ArgumentKind::Binding));
+
+        let mut cvars = Cvars::default();
+        cvars.register(Cvar::new("cam_speed", CvarValue::Number(15.0), "Camera speed"));
+        cvars.register(Cvar::new("clear_color", CvarValue::Color(Color::BLACK), "Background"));
+        (commands, cvars)
+    }
+
+    #[test]
+    fn command_names_are_completed() {
+        let (commands, cvars) = create_sources();
+        let sources = CompletionSources { commands: &commands, cvars: Some(&cvars) };
+
+        let completion = sources.complete("sp");
+        assert_eq!(completion.start, 0);
+        assert_eq!(completion.candidates, vec!["spawn", "spectator"]);
+        assert_eq!(completion.replacement(), Some("sp".to_string()));
+
+        let completion = sources.complete("spe");
+        assert_eq!(completion.replacement(), Some("spectator ".to_string()));
+    }
+
+    #[test]
+    fn arguments_are_completed_by_kind() {
+        let (commands, cvars) = create_sources();
+        let sources = CompletionSources { commands: &commands, cvars: Some(&cvars) };
+
+        let completion = sources.complete("set c");
+        assert_eq!(completion.start, 4);
+        assert_eq!(completion.candidates, vec!["cam_speed", "clear_color"]);
+
+        let completion = sources.complete("bindings movef");
+        assert_eq!(completion.replacement(), Some("MoveForward ".to_string()));
+
+        let completion = sources.complete("spawn cube ");
+        assert_eq!(completion.candidates, vec!["color="]);
+
+        let completion = sources.complete("spawn cube color=re");
+        assert_eq!(completion.start, 11);
+        assert_eq!(completion.candidates, vec!["color=red"]);
+
+        // Variadic values are free text
+        assert!(sources.complete("set cam_speed ").candidates.is_empty());
+        assert!(sources.complete("unknown ").candidates.is_empty());
+    }
+}
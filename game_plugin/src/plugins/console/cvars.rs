@@ -0,0 +1,462 @@
+/// Console variables (cvars): typed, named gameplay tunables, readable and settable from the console.
+///     e.g. `set cam_speed 30`, `get gravity`, `reset cam_speed`.
+///     Systems react to changes through `Res<Cvars>::is_changed()`, or the `CvarChanged` event.
+use std::collections::HashMap;
+use std::fmt;
+
+use bevy::prelude::*;
+use bevy::app::Events;
+
+use crate::plugins::console::commands::{CommandError, CommandResult};
+use crate::plugins::console::parser::{CommandLine, Value};
+use crate::utils::config;
+
+
+/// Name of the file persisting cvars, within the user configuration directory
+pub const CVARS_FILE: &str = "cvars.yaml";
+
+
+/// Sent whenever a cvar is set or reset from the console
+pub struct CvarChanged(pub String);
+
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CvarValue {
+    Bool(bool),
+    Number(f32),
+    Vector(Vec3),
+    Color(Color),
+}
+
+impl CvarValue {
+    fn type_name(&self) -> &'static str {
+        match self {
+            CvarValue::Bool(_) => "a boolean",
+            CvarValue::Number(_) => "a number",
+            CvarValue::Vector(_) => "a vector or 3 numbers",
+            CvarValue::Color(_) => "a color name or hex code",
+        }
+    }
+
+    /// Read the positional arguments starting at `index` as a value of the same type as this one
+    fn parse_arguments(&self, command: &CommandLine, index: usize) -> Option<CvarValue> {
+        let value = command.argument(index)?;
+        let parsed = match self {
+            CvarValue::Bool(_) => value.as_bool().map(CvarValue::Bool),
+            CvarValue::Number(_) => value.as_f32().map(CvarValue::Number),
+            CvarValue::Vector(_) => command.vec3_at(index).ok().map(CvarValue::Vector),
+            CvarValue::Color(_) => value.as_color().map(CvarValue::Color),
+        };
+
+        let width = match &parsed {
+            Some(CvarValue::Vector(_)) if value.as_vec3().is_none() => 3,
+            _default => 1,
+        };
+        parsed.filter(|_| command.arguments.len() == index + width)
+    }
+
+    fn parse_text(&self, text: &str) -> Option<CvarValue> {
+        let command = CommandLine {
+            name: String::new(),
+            arguments: text.split_whitespace().map(Value::parse).collect(),
+            named_arguments: Vec::new(),
+        };
+        self.parse_arguments(&command, 0)
+    }
+}
+
+impl fmt::Display for CvarValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CvarValue::Bool(value) => write!(f, "{}", value),
+            CvarValue::Number(value) => write!(f, "{}", value),
+            CvarValue::Vector(value) => write!(f, "{},{},{}", value.x, value.y, value.z),
+            CvarValue::Color(value) => {
+                let [r, g, b, a] = value.as_rgba_f32();
+                let to_byte = |it: f32| (it.clamp(0.0, 1.0) * 255.0).round() as u8;
+                write!(f, "#{:02x}{:02x}{:02x}{:02x}", to_byte(r), to_byte(g), to_byte(b), to_byte(a))
+            },
+        }
+    }
+}
+
+
+/// A console variable, with its default value and valid range
+#[derive(Debug, Clone)]
+pub struct Cvar {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub default: CvarValue,
+    pub value: CvarValue,
+    /// Inclusive range, for numbers and integers
+    pub min: Option<f32>,
+    pub max: Option<f32>,
+    /// Saved to the user's cvars file, and restored on the next session
+    pub is_persistent: bool,
+}
+
+impl Cvar {
+    pub fn new(name: &'static str, default: CvarValue, description: &'static str) -> Cvar {
+        Cvar {
+            name,
+            description,
+            value: default.clone(),
+            default,
+            min: None,
+            max: None,
+            is_persistent: false,
+        }
+    }
+
+    pub fn range(mut self, min: f32, max: f32) -> Self {
+        self.min = Some(min);
+        self.max = Some(max);
+        self
+    }
+
+    pub fn persistent(mut self) -> Self {
+        self.is_persistent = true;
+        self
+    }
+
+    fn check_range(&self, value: &CvarValue) -> Result<(), String> {
+        let number = match value {
+            CvarValue::Number(value) => *value,
+            _default => return Ok(()),
+        };
+
+        let is_too_low = self.min.map_or(false, |min| number < min);
+        let is_too_high = self.max.map_or(false, |max| number > max);
+        if is_too_low || is_too_high {
+            Err(format!("{} must be within [{}, {}]", self.name,
+                self.min.map_or("-inf".to_string(), |it| it.to_string()),
+                self.max.map_or("inf".to_string(), |it| it.to_string())))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl fmt::Display for Cvar {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} = {} (default {})", self.name, self.value, self.default)?;
+        if let (Some(min), Some(max)) = (self.min, self.max) {
+            write!(f, " [{}, {}]", min, max)?;
+        }
+        write!(f, " - {}", self.description)
+    }
+}
+
+
+/// Every registered cvar, plus the values persisted by the previous sessions (as text, by cvar name)
+#[derive(Debug, Default)]
+pub struct Cvars {
+    cvars: Vec<Cvar>,
+    persisted: HashMap<String, String>,
+}
+
+impl Cvars {
+    /// Read the values persisted by the previous sessions. A missing file simply means there are none.
+    pub fn load() -> Cvars {
+        let persisted = config::get_user_config_file(CVARS_FILE)
+            .filter(|path| path.exists())
+            .and_then(|path| match std::fs::read(&path) {
+                Ok(bytes) => serde_yaml::from_slice(&bytes)
+                    .map_err(|error| error!("Unable to parse '{}': {}", path.display(), error))
+                    .ok(),
+                Err(error) => {
+                    error!("Unable to read '{}': {}", path.display(), error);
+                    None
+                },
+            })
+            .unwrap_or_default();
+
+        Cvars { cvars: Vec::new(), persisted }
+    }
+
+    /// Register a cvar, restoring its persisted value if it has one
+    pub fn register(&mut self, mut cvar: Cvar) {
+        if let Some(text) = self.persisted.get(cvar.name) {
+            match cvar.default.parse_text(text).filter(|it| cvar.check_range(it).is_ok()) {
+                Some(value) => cvar.value = value,
+                None => warn!("Ignoring invalid persisted value for {}: '{}'", cvar.name, text),
+            }
+        }
+
+        match self.cvars.iter_mut().find(|it| it.name == cvar.name) {
+            Some(existing) => {
+                warn!("Cvar '{}' was registered twice; keeping the last one", cvar.name);
+                *existing = cvar;
+            },
+            None => self.cvars.push(cvar),
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Cvar> {
+        self.cvars.iter().find(|it| it.name == name)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Cvar> {
+        self.cvars.iter()
+    }
+
+    /// Typed getters, for systems: `None` if the cvar is not registered, or not of that type
+    pub fn bool(&self, name: &str) -> Option<bool> {
+        match &self.get(name)?.value {
+            CvarValue::Bool(value) => Some(*value),
+            _default => None,
+        }
+    }
+
+    pub fn number(&self, name: &str) -> Option<f32> {
+        match &self.get(name)?.value {
+            CvarValue::Number(value) => Some(*value),
+            _default => None,
+        }
+    }
+
+    pub fn vector(&self, name: &str) -> Option<Vec3> {
+        match &self.get(name)?.value {
+            CvarValue::Vector(value) => Some(*value),
+            _default => None,
+        }
+    }
+
+    pub fn color(&self, name: &str) -> Option<Color> {
+        match &self.get(name)?.value {
+            CvarValue::Color(value) => Some(*value),
+            _default => None,
+        }
+    }
+
+    /// Set a cvar from console arguments, starting at `index`
+    pub fn set(&mut self, name: &str, command: &CommandLine, index: usize) -> Result<&Cvar, String> {
+        let cvar = self.cvars.iter_mut().find(|it| it.name == name)
+            .ok_or_else(|| format!("Unknown cvar: '{}'. Type 'cvars' for a list of cvars", name))?;
+
+        let value = cvar.default.parse_arguments(command, index)
+            .ok_or_else(|| format!("{} expects {}", cvar.name, cvar.default.type_name()))?;
+        cvar.check_range(&value)?;
+
+        cvar.value = value;
+        Ok(cvar)
+    }
+
+    /// Set a cvar from a system, e.g. to reflect a setting changed without the console
+    pub fn set_value(&mut self, name: &str, value: CvarValue) -> Result<&Cvar, String> {
+        let cvar = self.cvars.iter_mut().find(|it| it.name == name)
+            .ok_or_else(|| format!("Unknown cvar: '{}'", name))?;
+
+        if value.type_name() != cvar.default.type_name() {
+            return Err(format!("{} expects {}", cvar.name, cvar.default.type_name()));
+        }
+        cvar.check_range(&value)?;
+
+        cvar.value = value;
+        Ok(cvar)
+    }
+
+    pub fn reset(&mut self, name: &str) -> Result<&Cvar, String> {
+        let cvar = self.cvars.iter_mut().find(|it| it.name == name)
+            .ok_or_else(|| format!("Unknown cvar: '{}'", name))?;
+
+        cvar.value = cvar.default.clone();
+        Ok(cvar)
+    }
+
+    /// Write every persistent cvar that differs from its default to the user's cvars file
+    pub fn save(&mut self) -> Result<(), String> {
+        let Cvars { cvars, persisted } = self;
+        cvars.iter()
+            .filter(|it| it.is_persistent)
+            .for_each(|it| {
+                if it.value == it.default {
+                    persisted.remove(it.name);
+                } else {
+                    persisted.insert(it.name.to_string(), it.value.to_string());
+                }
+            });
+
+        let contents = serde_yaml::to_string(persisted)
+            .map_err(|error| format!("Unable to serialize cvars: {}", error))?;
+        config::write_user_config_file(CVARS_FILE, &contents)
+            .map(|_| ())
+            .map_err(|error| format!("Unable to save cvars: {}", error))
+    }
+}
+
+
+/// Lets any plugin register cvars
+pub trait AddCvar {
+    fn add_cvar(&mut self, cvar: Cvar) -> &mut Self;
+}
+
+impl AddCvar for AppBuilder {
+    fn add_cvar(&mut self, cvar: Cvar) -> &mut Self {
+        self.world_mut()
+            .get_resource_or_insert_with(Cvars::load)
+            .register(cvar);
+        self
+    }
+}
+
+
+/// Commands
+/// `set <cvar> <value...>`
+pub fn set(world: &mut World, command: &CommandLine) -> CommandResult {
+    let name = command.require(0, "a cvar name", |it| Some(it.to_string()))?;
+    update_cvar(world, &name, |cvars| cvars.set(&name, command, 1).map(Cvar::to_string))
+}
+
+/// `reset <cvar>`
+pub fn reset(world: &mut World, command: &CommandLine) -> CommandResult {
+    let name = command.require(0, "a cvar name", |it| Some(it.to_string()))?;
+    update_cvar(world, &name, |cvars| cvars.reset(&name).map(Cvar::to_string))
+}
+
+/// `get <cvar>`
+pub fn get(world: &mut World, command: &CommandLine) -> CommandResult {
+    let name = command.require(0, "a cvar name", |it| Some(it.to_string()))?;
+    let cvars = get_cvars(world)?;
+    cvars.get(&name)
+        .map(Cvar::to_string)
+        .ok_or_else(|| CommandError::Failed(format!("Unknown cvar: '{}'. Type 'cvars' for a list of cvars", name)))
+}
+
+/// `cvars`: list every cvar
+pub fn list(world: &mut World, _command: &CommandLine) -> CommandResult {
+    let mut lines: Vec<String> = get_cvars(world)?.iter().map(Cvar::to_string).collect();
+    lines.sort();
+    Ok(lines.join("\n"))
+}
+
+fn get_cvars(world: &World) -> Result<&Cvars, CommandError> {
+    world.get_resource::<Cvars>()
+        .ok_or_else(|| CommandError::Failed("No cvars are registered".to_string()))
+}
+
+/// Apply a change, then persist it and notify the systems
+fn update_cvar(
+    world: &mut World,
+    name: &str,
+    update: impl FnOnce(&mut Cvars) -> Result<String, String>,
+) -> CommandResult {
+    let message = {
+        let mut cvars = world.get_resource_mut::<Cvars>()
+            .ok_or_else(|| CommandError::Failed("No cvars are registered".to_string()))?;
+
+        let message = update(&mut cvars)?;
+        if cvars.get(name).map_or(false, |it| it.is_persistent) {
+            if let Err(error) = cvars.save() {
+                error!("{}", error);
+            }
+        }
+        message
+    };
+
+    if let Some(mut cvar_changed) = world.get_resource_mut::<Events<CvarChanged>>() {
+        cvar_changed.send(CvarChanged(name.to_string()));
+    }
+    Ok(message)
+}
+
+
+/// Systems
+/// The background color of the game, formerly hard-coded in `main.rs`
+pub fn apply_clear_color(cvars: Res<Cvars>, mut clear_color: ResMut<ClearColor>) {
+    if cvars.is_changed() {
+        clear_color.0 = cvars.color("clear_color").unwrap_or(clear_color.0);
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+
+    use crate::plugins::console::parser::parse_command_line;
+    use super::{Cvar, CvarValue, Cvars};
+
+    fn create_cvars() -> Cvars {
+        let mut cvars = Cvars::default();
+        cvars.register(Cvar::new("cam_speed", CvarValue::Number(15.0), "Camera speed").range(0.0, 100.0));
+        cvars.register(Cvar::new("gravity", CvarValue::Vector(Vec3::new(0., -9.81, 0.)), "Gravity"));
+        cvars.register(Cvar::new("invert_y", CvarValue::Bool(false), "Invert Y"));
+        cvars
+    }
+
+    fn set(cvars: &mut Cvars, line: &str) -> Result<String, String> {
+        let command = parse_command_line(line).unwrap();
+        let name = command.argument(0).unwrap().to_string();
+        cvars.set(&name, &command, 1).map(|it| it.to_string())
+    }
+
+    #[test]
+    fn set_and_reset() {
+        let mut cvars = create_cvars();
+
+        assert!(set(&mut cvars, "set cam_speed 30").is_ok());
+        assert_eq!(cvars.number("cam_speed"), Some(30.0));
+
+        assert!(set(&mut cvars, "set invert_y true").is_ok());
+        assert_eq!(cvars.bool("invert_y"), Some(true));
+
+        assert!(set(&mut cvars, "set gravity 0 -1.62 0").is_ok());
+        assert_eq!(cvars.vector("gravity"), Some(Vec3::new(0., -1.62, 0.)));
+        assert!(set(&mut cvars, "set gravity 1,2,3").is_ok());
+        assert_eq!(cvars.vector("gravity"), Some(Vec3::new(1., 2., 3.)));
+
+        assert!(cvars.reset("cam_speed").is_ok());
+        assert_eq!(cvars.number("cam_speed"), Some(15.0));
+    }
+
+    #[test]
+    fn typed_getters_check_the_name_and_type() {
+        let cvars = create_cvars();
+
+        assert_eq!(cvars.number("nothing"), None);
+        assert_eq!(cvars.bool("cam_speed"), None);
+        assert_eq!(cvars.vector("invert_y"), None);
+    }
+
+    #[test]
+    fn invalid_values_are_rejected() {
+        let mut cvars = create_cvars();
+
+        assert!(set(&mut cvars, "set cam_speed 1000").is_err());
+        assert!(set(&mut cvars, "set cam_speed fast").is_err());
+        assert!(set(&mut cvars, "set cam_speed 1 2").is_err());
+        assert!(set(&mut cvars, "set gravity 0 -1").is_err());
+        assert!(set(&mut cvars, "set nothing 1").is_err());
+        assert!(cvars.set_value("cam_speed", CvarValue::Number(1000.0)).is_err());
+        assert!(cvars.set_value("cam_speed", CvarValue::Bool(true)).is_err());
+        assert_eq!(cvars.number("cam_speed"), Some(15.0));
+    }
+
+    #[test]
+    fn persisted_values_are_restored_on_register() {
+        let mut cvars = Cvars::default();
+        cvars.persisted.insert("cam_speed".to_string(), "42".to_string());
+        cvars.persisted.insert("clear_color".to_string(), "not-a-color".to_string());
+
+        cvars.register(Cvar::new("cam_speed", CvarValue::Number(15.0), "Camera speed").persistent());
+        cvars.register(Cvar::new("clear_color", CvarValue::Color(Color::BLACK), "Background").persistent());
+
+        assert_eq!(cvars.number("cam_speed"), Some(42.0));
+        assert_eq!(cvars.color("clear_color"), Some(Color::BLACK));
+    }
+
+    #[test]
+    fn values_round_trip_through_text() {
+        let values = vec![
+            CvarValue::Bool(true),
+            CvarValue::Number(0.25),
+            CvarValue::Vector(Vec3::new(0., -9.81, 0.)),
+            CvarValue::Color(Color::rgba(1.0, 0.0, 0.0, 1.0)),
+        ];
+
+        values.iter().for_each(|value| {
+            assert_eq!(value.parse_text(&value.to_string()).as_ref(), Some(value));
+        });
+    }
+}
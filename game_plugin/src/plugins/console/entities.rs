@@ -0,0 +1,319 @@
+/// Console commands to debug the scene: list, count, inspect, despawn and teleport.
+///     Entities are named as the console parses them (e.g. `3v0`), and filtered by marker component.
+use bevy::prelude::*;
+use bevy::ecs::reflect::ReflectComponent;
+use bevy::reflect::TypeRegistryArc;
+use bevy::transform::hierarchy::despawn_with_children_recursive;
+use heron::Velocity;
+
+use crate::plugins::console::commands::CommandResult;
+use crate::plugins::console::parser::{CommandLine, Value};
+use crate::plugins::player::{CubeActor, Possessed};
+use crate::plugins::spectator::SpectatorCamera;
+
+
+/// How far in front of the camera an entity ends up, after `teleport <entity>`
+const TELEPORT_DISTANCE: f32 = 10.0;
+
+
+/// Which entities a command applies to, by marker component
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntityFilter {
+    All,
+    CubeActor,
+    SpectatorCamera,
+    Possessed,
+}
+
+impl EntityFilter {
+    /// Every marker, in the order they are counted
+    pub const MARKERS: [EntityFilter; 3] = [
+        EntityFilter::CubeActor,
+        EntityFilter::SpectatorCamera,
+        EntityFilter::Possessed,
+    ];
+
+    /// Case-insensitive, with short names: `all`, `cube`, `spectator` or `possessed`
+    pub fn parse(name: &str) -> Option<EntityFilter> {
+        match name.to_lowercase().as_str() {
+            "all" => Some(EntityFilter::All),
+            "cube" | "cubeactor" => Some(EntityFilter::CubeActor),
+            "spectator" | "spectatorcamera" => Some(EntityFilter::SpectatorCamera),
+            "possessed" => Some(EntityFilter::Possessed),
+            _default => None,
+        }
+    }
+
+    /// Matching entities, sorted by id
+    pub fn entities(&self, world: &mut World) -> Vec<Entity> {
+        let mut entities: Vec<Entity> = match self {
+            EntityFilter::All => world.query::<Entity>().iter(world).collect(),
+            EntityFilter::CubeActor => world.query_filtered::<Entity, With<CubeActor>>().iter(world).collect(),
+            EntityFilter::SpectatorCamera => world.query_filtered::<Entity, With<SpectatorCamera>>().iter(world).collect(),
+            EntityFilter::Possessed => world.query_filtered::<Entity, With<Possessed>>().iter(world).collect(),
+        };
+        entities.sort_by_key(|it| it.id());
+        entities
+    }
+
+    fn matches(&self, world: &World, entity: Entity) -> bool {
+        match self {
+            EntityFilter::All => true,
+            EntityFilter::CubeActor => world.get::<CubeActor>(entity).is_some(),
+            EntityFilter::SpectatorCamera => world.get::<SpectatorCamera>(entity).is_some(),
+            EntityFilter::Possessed => world.get::<Possessed>(entity).is_some(),
+        }
+    }
+}
+
+
+/// Commands
+/// `entities [filter]`: one line per entity, with its markers and position
+pub fn list(world: &mut World, command: &CommandLine) -> CommandResult {
+    let filter = filter_argument(command, 0)?.unwrap_or(EntityFilter::All);
+    let lines: Vec<String> = filter.entities(world).into_iter()
+        .map(|it| describe(world, it))
+        .collect();
+
+    match lines.is_empty() {
+        true => Ok(format!("No entities match '{:?}'", filter)),
+        false => Ok(lines.join("\n")),
+    }
+}
+
+/// `count [filter]`: how many entities there are, in total and per marker (or for one filter)
+pub fn count(world: &mut World, command: &CommandLine) -> CommandResult {
+    if let Some(filter) = filter_argument(command, 0)? {
+        return Ok(format!("{:?}: {}", filter, filter.entities(world).len()));
+    }
+
+    let counts: Vec<String> = std::iter::once(EntityFilter::All)
+        .chain(EntityFilter::MARKERS.iter().copied())
+        .map(|it| format!("{:?}: {}", it, it.entities(world).len()))
+        .collect();
+    Ok(counts.join(", "))
+}
+
+/// `inspect <entity>`: its Transform, velocity, and every component (with its value, when it can be reflected)
+pub fn inspect(world: &mut World, command: &CommandLine) -> CommandResult {
+    let entity = command.require(0, "an entity id", Value::as_entity)?;
+    let entity_ref = world.get_entity(entity)
+        .ok_or_else(|| format!("No entity {}", format_entity(entity)))?;
+
+    let mut lines = vec![describe(world, entity)];
+    if let Some(transform) = entity_ref.get::<Transform>() {
+        lines.push(format!("  translation: {}", transform.translation));
+        lines.push(format!("  rotation: {}", transform.rotation));
+        lines.push(format!("  scale: {}", transform.scale));
+    }
+    if let Some(velocity) = entity_ref.get::<Velocity>() {
+        lines.push(format!("  linear velocity: {}", velocity.linear));
+        lines.push(format!("  angular velocity: {:?}", velocity.angular));
+    }
+
+    lines.push("  components:".to_string());
+    let type_registry = world.get_resource::<TypeRegistryArc>().map(TypeRegistryArc::read);
+    entity_ref.archetype().components()
+        .filter_map(|id| world.components().get_info(id))
+        .for_each(|info| {
+            let value = info.type_id()
+                .and_then(|type_id| type_registry.as_ref()?.get(type_id))
+                .and_then(|registration| registration.data::<ReflectComponent>())
+                .and_then(|reflect_component| reflect_component.reflect_component(world, entity));
+            match value {
+                Some(value) => lines.push(format!("    {} = {:?}", short_type_name(info.name()), value)),
+                None => lines.push(format!("    {}", short_type_name(info.name()))),
+            }
+        });
+
+    Ok(lines.join("\n"))
+}
+
+/// `despawn <entity | filter>`: despawn one entity, or every entity matching a marker, with their children
+pub fn despawn(world: &mut World, command: &CommandLine) -> CommandResult {
+    let entities = match command.argument(0).and_then(Value::as_entity) {
+        Some(entity) if world.get_entity(entity).is_some() => vec![entity],
+        Some(entity) => return Err(format!("No entity {}", format_entity(entity)).into()),
+        None => match filter_argument(command, 0)? {
+            // Despawning everything would also remove the UI, the lights, the floor...
+            Some(EntityFilter::All) => return Err("Refusing to despawn every entity: use a marker".to_string().into()),
+            Some(filter) => filter.entities(world),
+            None => return Err("Expected an entity id, or cube, spectator or possessed".to_string().into()),
+        },
+    };
+
+    if entities.is_empty() {
+        return Ok("No entities to despawn".to_string());
+    }
+    entities.iter().for_each(|it| despawn_with_children_recursive(world, *it));
+    Ok(format!("Despawned {} entities", entities.len()))
+}
+
+/// `teleport <x y z | entity>`: move the Possessed camera, keeping its rotation.
+///     Teleporting to an entity leaves it in front of the camera.
+pub fn teleport(world: &mut World, command: &CommandLine) -> CommandResult {
+    let camera = EntityFilter::Possessed.entities(world).into_iter().next()
+        .ok_or_else(|| "Nothing is Possessed".to_string())?;
+    let rotation = world.get::<Transform>(camera)
+        .map(|it| it.rotation)
+        .ok_or_else(|| "The Possessed entity has no Transform".to_string())?;
+
+    let translation = match command.argument(0).and_then(Value::as_entity) {
+        Some(target) if target == camera => return Err("Cannot teleport to the Possessed entity itself".to_string().into()),
+        Some(target) => {
+            let target_translation = world.get::<GlobalTransform>(target).map(|it| it.translation)
+                .or_else(|| world.get::<Transform>(target).map(|it| it.translation))
+                .ok_or_else(|| format!("Entity {} has no position", format_entity(target)))?;
+            target_translation - rotation.mul_vec3(-Vec3::Z) * TELEPORT_DISTANCE
+        },
+        None => command.vec3_at(0)?,
+    };
+
+    if let Some(mut transform) = world.get_mut::<Transform>(camera) {
+        transform.translation = translation;
+    }
+    Ok(format!("Teleported {} to {}", format_entity(camera), translation))
+}
+
+
+/// Helpers
+/// `3v0 CubeActor Possessed at [0, 10, 0]`
+pub fn describe(world: &World, entity: Entity) -> String {
+    let mut text = format_entity(entity);
+    if let Some(name) = world.get::<Name>(entity) {
+        text.push_str(&format!(" '{}'", name.as_str()));
+    }
+    EntityFilter::MARKERS.iter()
+        .filter(|it| it.matches(world, entity))
+        .for_each(|it| text.push_str(&format!(" {:?}", it)));
+    if let Some(transform) = world.get::<Transform>(entity) {
+        text.push_str(&format!(" at {}", transform.translation));
+    }
+    text
+}
+
+/// As the console parses it, e.g. `3v0`
+fn format_entity(entity: Entity) -> String {
+    Value::Entity(entity).to_string()
+}
+
+fn filter_argument(command: &CommandLine, index: usize) -> Result<Option<EntityFilter>, String> {
+    command.argument(index)
+        .map(|it| EntityFilter::parse(&it.to_string())
+            .ok_or_else(|| format!("Unknown filter '{}': expected all, cube, spectator or possessed", it)))
+        .transpose()
+}
+
+/// `heron_core::Velocity` into `Velocity`, and `bevy_asset::handle::Handle<bevy_pbr::StandardMaterial>`
+///     into `Handle<StandardMaterial>`
+fn short_type_name(name: &str) -> String {
+    let mut short = String::new();
+    let mut segment = String::new();
+
+    name.chars().for_each(|it| match it {
+        '<' | '>' | ',' | ' ' | '(' | ')' | '[' | ']' | ';' | '&' => {
+            short.push_str(segment.rsplit("::").next().unwrap_or_default());
+            segment.clear();
+            short.push(it);
+        },
+        _default => segment.push(it),
+    });
+    short.push_str(segment.rsplit("::").next().unwrap_or_default());
+    short
+}
+
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+
+    use crate::plugins::console::commands::CommandResult;
+    use crate::plugins::console::parser::{CommandLine, parse_command_line};
+    use crate::plugins::player::{CubeActor, Possessed};
+    use crate::plugins::spectator::SpectatorCamera;
+    use super::{count, despawn, inspect, list, short_type_name, teleport};
+
+    /// Two cubes, and a Possessed camera looking down -Z
+    fn create_world() -> (World, Entity, Entity) {
+        let mut world = World::default();
+        let cube = world.spawn()
+            .insert_bundle((Transform::from_xyz(0.0, 10.0, 0.0), CubeActor))
+            .id();
+        world.spawn().insert_bundle((Transform::from_xyz(5.0, 0.0, 0.0), CubeActor));
+        let camera = world.spawn()
+            .insert_bundle((Transform::identity(), SpectatorCamera, Possessed))
+            .id();
+        (world, cube, camera)
+    }
+
+    fn run(world: &mut World, handler: fn(&mut World, &CommandLine) -> CommandResult, line: &str) -> Result<String, String> {
+        handler(world, &parse_command_line(line).unwrap()).map_err(|it| it.to_string())
+    }
+
+    #[test]
+    fn entities_are_listed_and_counted_by_marker() {
+        let (mut world, cube, _) = create_world();
+
+        let cubes = run(&mut world, list, "entities cube").unwrap();
+        assert_eq!(cubes.lines().count(), 2);
+        assert!(cubes.starts_with(&format!("{}v0 CubeActor at", cube.id())), "{}", cubes);
+
+        assert_eq!(run(&mut world, count, "count").unwrap(), "All: 3, CubeActor: 2, SpectatorCamera: 1, Possessed: 1");
+        assert_eq!(run(&mut world, count, "count possessed").unwrap(), "Possessed: 1");
+        assert!(run(&mut world, count, "count nope").is_err());
+    }
+
+    #[test]
+    fn entities_are_inspected() {
+        let (mut world, cube, _) = create_world();
+
+        let inspected = run(&mut world, inspect, &format!("inspect {}v0", cube.id())).unwrap();
+        assert!(inspected.contains("translation: [0, 10, 0]"), "{}", inspected);
+        assert!(inspected.contains("    CubeActor"), "{}", inspected);
+        assert!(run(&mut world, inspect, "inspect 999v0").is_err());
+    }
+
+    #[test]
+    fn entities_are_despawned_by_id_or_marker() {
+        let (mut world, cube, camera) = create_world();
+
+        assert_eq!(run(&mut world, despawn, &format!("despawn {}v0", cube.id())).unwrap(), "Despawned 1 entities");
+        assert!(world.get_entity(cube).is_none());
+        assert!(run(&mut world, despawn, "despawn all").is_err());
+
+        assert_eq!(run(&mut world, despawn, "despawn cube").unwrap(), "Despawned 1 entities");
+        assert_eq!(run(&mut world, count, "count cube").unwrap(), "CubeActor: 0");
+        assert!(world.get_entity(camera).is_some());
+
+        // A despawned child is removed from its parent, and takes its own children along
+        let child = world.spawn().insert(CubeActor).id();
+        let grandchild = world.spawn().id();
+        world.entity_mut(child).push_children(&[grandchild]);
+        world.entity_mut(camera).push_children(&[child]);
+        run(&mut world, despawn, &format!("despawn {}v{}", child.id(), child.generation())).unwrap();
+        assert!(world.get_entity(grandchild).is_none());
+        assert!(world.get::<Children>(camera).map_or(true, |it| !it.contains(&child)));
+    }
+
+    #[test]
+    fn possessed_camera_is_teleported() {
+        let (mut world, cube, camera) = create_world();
+
+        run(&mut world, teleport, "teleport 1 2 3").unwrap();
+        assert_eq!(world.get::<Transform>(camera).unwrap().translation, Vec3::new(1.0, 2.0, 3.0));
+
+        // In front of the camera, which looks down -Z
+        run(&mut world, teleport, &format!("teleport {}v0", cube.id())).unwrap();
+        assert_eq!(world.get::<Transform>(camera).unwrap().translation, Vec3::new(0.0, 10.0, 10.0));
+        assert!(run(&mut world, teleport, &format!("teleport {}v0", camera.id())).is_err());
+    }
+
+    #[test]
+    fn type_names_are_shortened() {
+        assert_eq!(short_type_name("heron_core::Velocity"), "Velocity");
+        assert_eq!(
+            short_type_name("bevy_asset::handle::Handle<bevy_pbr::material::StandardMaterial>"),
+            "Handle<StandardMaterial>"
+        );
+    }
+}
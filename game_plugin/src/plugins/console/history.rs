@@ -0,0 +1,220 @@
+/// History of the commands entered in the console: Up/Down navigation, and Ctrl+R reverse search.
+///     Persisted between sessions in the user's configuration directory, one command per line.
+use bevy::prelude::*;
+
+use crate::utils::config;
+
+
+/// Name of the file persisting the history, within the user configuration directory
+pub const HISTORY_FILE: &str = "console_history.txt";
+
+/// Oldest commands are forgotten beyond this many entries
+const MAX_ENTRIES: usize = 500;
+
+
+#[derive(Debug, Default, Clone)]
+pub struct CommandHistory {
+    entries: Vec<String>,
+    /// Index of the entry shown while navigating with Up/Down, if any
+    position: Option<usize>,
+    /// What was being typed before navigating, restored when going past the newest entry
+    draft: String,
+}
+
+impl CommandHistory {
+    pub fn new(entries: Vec<String>) -> CommandHistory {
+        let mut history = CommandHistory::default();
+        entries.iter().for_each(|it| history.push(it));
+        history
+    }
+
+    /// Read the history of the previous sessions. A missing file simply means there is none.
+    pub fn load() -> CommandHistory {
+        let entries = config::get_user_config_file(HISTORY_FILE)
+            .filter(|path| path.exists())
+            .and_then(|path| std::fs::read_to_string(&path)
+                .map_err(|error| error!("Unable to read '{}': {}", path.display(), error))
+                .ok())
+            .map(|contents| contents.lines().map(str::to_string).collect())
+            .unwrap_or_default();
+
+        CommandHistory::new(entries)
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        config::write_user_config_file(HISTORY_FILE, &self.entries.join("\n"))
+            .map(|_| ())
+            .map_err(|error| format!("Unable to save the console history: {}", error))
+    }
+
+    pub fn entries(&self) -> &[String] {
+        &self.entries
+    }
+
+    pub fn get(&self, index: usize) -> Option<&str> {
+        self.entries.get(index).map(String::as_str)
+    }
+
+    /// Remember an entered command, unless it repeats the previous one. Ends any Up/Down navigation.
+    pub fn push(&mut self, line: &str) {
+        self.reset_navigation();
+
+        let line = line.trim();
+        if line.is_empty() || self.entries.last().map_or(false, |it| it == line) {
+            return;
+        }
+
+        self.entries.push(line.to_string());
+        if self.entries.len() > MAX_ENTRIES {
+            self.entries.remove(0);
+        }
+    }
+
+    /// Up: the entry before the one shown, starting from the newest. `current` is kept as the draft.
+    pub fn previous(&mut self, current: &str) -> Option<&str> {
+        let position = match self.position {
+            None if self.entries.is_empty() => return None,
+            None => {
+                self.draft = current.to_string();
+                self.entries.len() - 1
+            },
+            Some(position) => position.saturating_sub(1),
+        };
+
+        self.position = Some(position);
+        self.get(position)
+    }
+
+    /// Down: the entry after the one shown, then the draft once past the newest one
+    pub fn next(&mut self) -> Option<&str> {
+        let position = self.position?;
+        if position + 1 < self.entries.len() {
+            self.position = Some(position + 1);
+            self.get(position + 1)
+        } else {
+            self.position = None;
+            Some(&self.draft)
+        }
+    }
+
+    pub fn reset_navigation(&mut self) {
+        self.position = None;
+        self.draft.clear();
+    }
+
+    /// Index of the newest entry older than `before` which contains `query`
+    pub fn search(&self, query: &str, before: usize) -> Option<usize> {
+        let query = query.to_lowercase();
+        self.entries[..before.min(self.entries.len())].iter()
+            .rposition(|it| it.to_lowercase().contains(&query))
+    }
+}
+
+
+/// State of a Ctrl+R reverse search, while it is active
+#[derive(Debug, Default, Clone)]
+pub struct ReverseSearch {
+    pub is_active: bool,
+    pub query: String,
+    /// Index of the matching history entry
+    pub found: Option<usize>,
+    /// The command line before the search started, restored if it is cancelled
+    pub original_line: String,
+}
+
+impl ReverseSearch {
+    pub fn start(&mut self, original_line: &str) {
+        *self = ReverseSearch {
+            is_active: true,
+            original_line: original_line.to_string(),
+            ..Default::default()
+        };
+    }
+
+    pub fn stop(&mut self) {
+        *self = ReverseSearch::default();
+    }
+
+    /// Search again from the newest entry, after the query has changed
+    pub fn update(&mut self, history: &CommandHistory) {
+        self.found = history.search(&self.query, history.entries().len());
+    }
+
+    /// Ctrl+R again: the next older match, keeping the current one if there is none
+    pub fn search_older(&mut self, history: &CommandHistory) {
+        let before = self.found.unwrap_or_else(|| history.entries().len());
+        if let Some(found) = history.search(&self.query, before) {
+            self.found = Some(found);
+        }
+    }
+
+    /// e.g. (reverse-i-search)`cub': spawn cube
+    pub fn prompt(&self, history: &CommandHistory) -> String {
+        let found = self.found.and_then(|it| history.get(it)).unwrap_or("");
+        let status = if self.found.is_none() && !self.query.is_empty() { "failing " } else { "" };
+        format!("({}reverse-i-search)`{}': {}", status, self.query, found)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::{CommandHistory, ReverseSearch};
+
+    fn create_history() -> CommandHistory {
+        CommandHistory::new(vec![
+            "spawn cube".to_string(),
+            "set cam_speed 30".to_string(),
+            "spawn cube 0 10 0".to_string(),
+            "help".to_string(),
+        ])
+    }
+
+    #[test]
+    fn up_and_down_navigate_and_restore_the_draft() {
+        let mut history = create_history();
+
+        assert_eq!(history.previous("spa"), Some("help"));
+        assert_eq!(history.previous("help"), Some("spawn cube 0 10 0"));
+        assert_eq!(history.next(), Some("help"));
+        assert_eq!(history.next(), Some("spa"));
+        assert_eq!(history.next(), None);
+
+        (0..10).for_each(|_| { history.previous(""); });
+        assert_eq!(history.previous(""), Some("spawn cube"));
+    }
+
+    #[test]
+    fn repeated_and_empty_commands_are_not_remembered() {
+        let mut history = create_history();
+        history.push("help");
+        history.push("   ");
+        history.push(" exit ");
+        assert_eq!(history.entries().len(), 5);
+        assert_eq!(history.get(4), Some("exit"));
+    }
+
+    #[test]
+    fn reverse_search_finds_older_matches() {
+        let history = create_history();
+        let mut search = ReverseSearch::default();
+        search.start("");
+
+        search.query = "CUBE".to_string();
+        search.update(&history);
+        assert_eq!(search.found, Some(2));
+
+        search.search_older(&history);
+        assert_eq!(search.found, Some(0));
+
+        // No older match: keep the current one
+        search.search_older(&history);
+        assert_eq!(search.found, Some(0));
+        assert_eq!(search.prompt(&history), "(reverse-i-search)`CUBE': spawn cube");
+
+        search.query = "nothing".to_string();
+        search.update(&history);
+        assert_eq!(search.found, None);
+        assert!(search.prompt(&history).starts_with("(failing reverse-i-search)"));
+    }
+}
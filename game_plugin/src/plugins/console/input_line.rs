@@ -0,0 +1,224 @@
+/// The console's command line: the text being typed, and a movable caret within it.
+///     Printable characters come from `ReceivedCharacter` events, so that the keyboard layout, Shift and
+///     dead keys are handled by the OS; editing keys (arrows, Home/End, Delete...) come from `Input<KeyCode>`.
+
+
+/// Rendered at the caret's position in the `ConsoleTextInput` widget
+pub const CARET: &str = "|";
+
+
+/// The text being typed in the console. The caret is a char index (not a byte index) into the text.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ConsoleInputLine {
+    text: String,
+    caret: usize,
+}
+
+impl ConsoleInputLine {
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.text.is_empty()
+    }
+
+    /// Replace the whole line, with the caret at its end
+    pub fn set_text(&mut self, text: &str) {
+        self.text = text.to_string();
+        self.caret = self.len();
+    }
+
+    /// Empty the line, returning what it contained
+    pub fn take(&mut self) -> String {
+        self.caret = 0;
+        std::mem::take(&mut self.text)
+    }
+
+    /// The text before and after the caret, for rendering
+    pub fn split_at_caret(&self) -> (&str, &str) {
+        self.text.split_at(self.byte_index(self.caret))
+    }
+
+    /// Insert a typed character at the caret. Control characters (Backspace, Enter, Escape...) are ignored.
+    pub fn insert(&mut self, character: char) {
+        if character.is_control() {
+            return;
+        }
+
+        let index = self.byte_index(self.caret);
+        self.text.insert(index, character);
+        self.caret += 1;
+    }
+
+    /// Replace the text from the char index `start` up to the caret, e.g. to complete the word being typed
+    pub fn replace_before_caret(&mut self, start: usize, text: &str) {
+        let start = start.min(self.caret);
+        let range = self.byte_index(start)..self.byte_index(self.caret);
+        self.text.replace_range(range, text);
+        self.caret = start + text.chars().count();
+    }
+
+    pub fn move_left(&mut self) {
+        self.caret = self.caret.saturating_sub(1);
+    }
+
+    pub fn move_right(&mut self) {
+        self.caret = (self.caret + 1).min(self.len());
+    }
+
+    pub fn move_home(&mut self) {
+        self.caret = 0;
+    }
+
+    pub fn move_end(&mut self) {
+        self.caret = self.len();
+    }
+
+    /// Backspace: remove the character before the caret
+    pub fn delete_back(&mut self) {
+        if self.caret > 0 {
+            self.caret -= 1;
+            let index = self.byte_index(self.caret);
+            self.text.remove(index);
+        }
+    }
+
+    /// Delete: remove the character after the caret
+    pub fn delete_forward(&mut self) {
+        if self.caret < self.len() {
+            let index = self.byte_index(self.caret);
+            self.text.remove(index);
+        }
+    }
+
+    /// Ctrl+Backspace: remove the word before the caret, along with the whitespace that follows it
+    pub fn delete_word_back(&mut self) {
+        let chars: Vec<char> = self.text.chars().collect();
+        let mut start = self.caret;
+        while start > 0 && chars[start - 1].is_whitespace() {
+            start -= 1;
+        }
+        while start > 0 && !chars[start - 1].is_whitespace() {
+            start -= 1;
+        }
+
+        let range = self.byte_index(start)..self.byte_index(self.caret);
+        self.text.replace_range(range, "");
+        self.caret = start;
+    }
+
+
+    /// Helpers
+    fn len(&self) -> usize {
+        self.text.chars().count()
+    }
+
+    fn byte_index(&self, caret: usize) -> usize {
+        self.text.char_indices()
+            .nth(caret)
+            .map_or(self.text.len(), |(index, _)| index)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::ConsoleInputLine;
+
+    fn typed(text: &str) -> ConsoleInputLine {
+        let mut input_line = ConsoleInputLine::default();
+        text.chars().for_each(|it| input_line.insert(it));
+        input_line
+    }
+
+    #[test]
+    fn typing_accepts_any_printable_character() {
+        let input_line = typed("Spawn cube 0,1.5,-2 color='#FF0000' é");
+        assert_eq!(input_line.text(), "Spawn cube 0,1.5,-2 color='#FF0000' é");
+
+        let input_line = typed("a\u{8}\r\u{1b}\tb");
+        assert_eq!(input_line.text(), "ab");
+    }
+
+    #[test]
+    fn caret_moves_and_inserts_in_place() {
+        let mut input_line = typed("spwn");
+        input_line.move_left();
+        input_line.move_left();
+        input_line.move_left();
+        input_line.insert('a');
+        assert_eq!(input_line.text(), "sapwn");
+
+        input_line.move_home();
+        input_line.insert('>');
+        input_line.move_end();
+        input_line.insert('!');
+        assert_eq!(input_line.text(), ">sapwn!");
+        assert_eq!(input_line.split_at_caret(), (">sapwn!", ""));
+
+        input_line.move_right();
+        assert_eq!(input_line.split_at_caret(), (">sapwn!", ""));
+    }
+
+    #[test]
+    fn deleting_on_an_empty_line_does_nothing() {
+        let mut input_line = ConsoleInputLine::default();
+        input_line.delete_back();
+        input_line.delete_forward();
+        input_line.delete_word_back();
+        input_line.move_left();
+        assert_eq!(input_line, ConsoleInputLine::default());
+    }
+
+    #[test]
+    fn delete_around_the_caret() {
+        let mut input_line = typed("héllo");
+        input_line.move_left();
+        input_line.delete_back();
+        input_line.delete_forward();
+        assert_eq!(input_line.text(), "hél");
+        assert_eq!(input_line.split_at_caret(), ("hél", ""));
+
+        input_line.move_home();
+        input_line.delete_back();
+        input_line.delete_forward();
+        assert_eq!(input_line.text(), "él");
+    }
+
+    #[test]
+    fn ctrl_backspace_deletes_the_previous_word() {
+        let mut input_line = typed("spawn cube  0,1,0");
+        input_line.delete_word_back();
+        assert_eq!(input_line.text(), "spawn cube  ");
+
+        input_line.delete_word_back();
+        assert_eq!(input_line.text(), "spawn ");
+
+        "sphere".chars().for_each(|it| input_line.insert(it));
+        input_line.move_home();
+        input_line.move_right();
+        input_line.move_right();
+        input_line.delete_word_back();
+        assert_eq!(input_line.split_at_caret(), ("", "awn sphere"));
+    }
+
+    #[test]
+    fn replace_the_word_before_the_caret() {
+        let mut input_line = typed("set cam 30");
+        input_line.move_left();
+        input_line.move_left();
+        input_line.move_left();
+        input_line.replace_before_caret(4, "cam_speed");
+        assert_eq!(input_line.text(), "set cam_speed 30");
+        assert_eq!(input_line.split_at_caret(), ("set cam_speed", " 30"));
+    }
+
+    #[test]
+    fn take_empties_the_line() {
+        let mut input_line = typed("help");
+        assert_eq!(input_line.take(), "help");
+        assert!(input_line.is_empty());
+        assert_eq!(input_line, ConsoleInputLine::default());
+    }
+}
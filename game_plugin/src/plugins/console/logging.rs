@@ -0,0 +1,270 @@
+/// Route the `tracing` (and `log`) output into the console, alongside stdout.
+///     Replaces Bevy's LogPlugin, which does not let us add our own layer: a `ConsoleLogLayer` buffers
+///     the records in a bounded ring buffer, which a system drains into the console every frame.
+///     The filter (e.g. `info,wgpu=error,game_plugin::plugins::input=debug`) can be changed at runtime
+///     with the `loglevel` console command, for both the console and stdout.
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+use bevy::prelude::*;
+use chrono::{DateTime, Local};
+use tracing::{Event, Level, Subscriber};
+use tracing::field::{Field, Visit};
+use tracing_subscriber::{EnvFilter, Registry, reload};
+use tracing_subscriber::layer::{Context, Layer, SubscriberExt};
+
+use crate::plugins::console::commands::CommandResult;
+use crate::plugins::console::parser::CommandLine;
+
+
+/// Used when `RUST_LOG` is not set, as Bevy's LogPlugin does
+pub const DEFAULT_FILTER: &str = "info,wgpu=error";
+
+/// Oldest records are dropped beyond this many, when the console does not drain them (e.g. in the menus)
+const MAX_BUFFERED_RECORDS: usize = 1000;
+
+
+/// Event: a log record, to be rendered in the console with its level's color
+#[derive(Debug, Clone)]
+pub struct ConsoleLogRecord {
+    pub level: Level,
+    pub target: String,
+    pub message: String,
+    pub timestamp: DateTime<Local>,
+}
+
+impl ConsoleLogRecord {
+    pub fn color(&self) -> Color {
+        match self.level {
+            Level::ERROR => Color::RED,
+            Level::WARN => Color::YELLOW,
+            Level::INFO => Color::WHITE,
+            Level::DEBUG => Color::rgb(0.6, 0.6, 0.6),
+            Level::TRACE => Color::rgb(0.6, 0.5, 0.8),
+        }
+    }
+}
+
+impl fmt::Display for ConsoleLogRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {:>5} {}: {}", self.timestamp.format("%H:%M:%S"), self.level, self.target, self.message)
+    }
+}
+
+
+/// Records waiting to be shown in the console, shared with the `ConsoleLogLayer`
+#[derive(Debug, Clone, Default)]
+pub struct ConsoleLogBuffer(Arc<Mutex<VecDeque<ConsoleLogRecord>>>);
+
+impl ConsoleLogBuffer {
+    pub fn push(&self, record: ConsoleLogRecord) {
+        if let Ok(mut records) = self.0.lock() {
+            if records.len() >= MAX_BUFFERED_RECORDS {
+                records.pop_front();
+            }
+            records.push_back(record);
+        }
+    }
+
+    pub fn drain(&self) -> Vec<ConsoleLogRecord> {
+        self.0.lock()
+            .map(|mut records| records.drain(..).collect())
+            .unwrap_or_default()
+    }
+}
+
+
+/// A tracing Layer, copying every enabled event into the `ConsoleLogBuffer`
+pub struct ConsoleLogLayer {
+    buffer: ConsoleLogBuffer,
+}
+
+impl<S: Subscriber> Layer<S> for ConsoleLogLayer {
+    fn on_event(&self, event: &Event<'_>, _context: Context<'_, S>) {
+        let mut visitor = MessageVisitor::default();
+        event.record(&mut visitor);
+
+        let metadata = event.metadata();
+        self.buffer.push(ConsoleLogRecord {
+            level: *metadata.level(),
+            target: metadata.target().to_string(),
+            message: visitor.message,
+            timestamp: Local::now(),
+        });
+    }
+}
+
+/// Formats an event's `message`, followed by its other fields as `name=value`
+#[derive(Default)]
+struct MessageVisitor {
+    message: String,
+}
+
+impl Visit for MessageVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if !self.message.is_empty() {
+            self.message.push(' ');
+        }
+
+        if field.name() == "message" {
+            self.message.push_str(&format!("{:?}", value));
+        } else {
+            self.message.push_str(&format!("{}={:?}", field.name(), value));
+        }
+    }
+}
+
+
+/// The filter directives in effect, and the handle to change them at runtime
+pub struct LogFilter {
+    pub directives: String,
+    handle: Option<reload::Handle<EnvFilter, Registry>>,
+}
+
+impl LogFilter {
+    /// Replace the directives, e.g. `debug` or `info,game_plugin::plugins::input=trace`
+    pub fn set(&mut self, directives: &str) -> Result<(), String> {
+        let filter = EnvFilter::try_new(directives)
+            .map_err(|error| format!("Invalid log filter '{}': {}", directives, error))?;
+        let handle = self.handle.as_ref()
+            .ok_or_else(|| "The console is not receiving the logs".to_string())?;
+
+        handle.reload(filter)
+            .map_err(|error| format!("Unable to change the log filter: {}", error))?;
+        self.directives = directives.to_string();
+        Ok(())
+    }
+
+    /// Set the level of one module (or of every module, when `module` is None), keeping the other directives
+    pub fn set_level(&mut self, level: &str, module: Option<&str>) -> Result<(), String> {
+        let directives = with_level(&self.directives, level, module);
+        self.set(&directives)
+    }
+}
+
+
+/// Install the global tracing subscriber: stdout, and the console's buffer.
+///     Must run before anything logs, which is why it replaces Bevy's LogPlugin.
+pub fn setup_logging() -> (LogFilter, ConsoleLogBuffer) {
+    let directives = std::env::var("RUST_LOG").unwrap_or_else(|_| DEFAULT_FILTER.to_string());
+    let filter = EnvFilter::try_new(&directives).unwrap_or_else(|_| EnvFilter::new(DEFAULT_FILTER));
+    let (filter_layer, handle) = reload::Layer::new(filter);
+
+    let buffer = ConsoleLogBuffer::default();
+    let subscriber = Registry::default()
+        .with(filter_layer)
+        .with(tracing_subscriber::fmt::Layer::default())
+        .with(ConsoleLogLayer { buffer: buffer.clone() });
+
+    // Also forward the records of crates using `log` rather than `tracing`
+    let _ = tracing_log::LogTracer::init();
+    let handle = match tracing::subscriber::set_global_default(subscriber) {
+        Ok(()) => Some(handle),
+        Err(_) => {
+            // e.g. Bevy's LogPlugin was not disabled, as on the web where it logs to the browser console
+            warn!("A global tracing subscriber is already set: logs will not be shown in the console");
+            None
+        },
+    };
+
+    (LogFilter { directives, handle }, buffer)
+}
+
+
+/// Commands
+/// `loglevel [level] [module]`: show the filter, or set the level of every module (or of one module)
+pub fn loglevel(world: &mut World, command: &CommandLine) -> CommandResult {
+    let mut log_filter = world.get_resource_mut::<LogFilter>()
+        .ok_or_else(|| "Logging is not set up".to_string())?;
+
+    match command.argument(0) {
+        None => Ok(format!("Log filter: {}", log_filter.directives)),
+        Some(level) => {
+            let level = level.to_string().to_lowercase();
+            if !["trace", "debug", "info", "warn", "error", "off"].contains(&level.as_str()) {
+                return Err(format!("Unknown log level '{}': expected trace, debug, info, warn, error or off", level).into());
+            }
+
+            let module = command.argument(1).map(|it| it.to_string());
+            log_filter.set_level(&level, module.as_deref())?;
+            Ok(format!("Log filter: {}", log_filter.directives))
+        },
+    }
+}
+
+
+/// Systems
+/// Forward the buffered records to the console
+pub fn drain_log_buffer(
+    buffer: Res<ConsoleLogBuffer>,
+    mut console_log_records: EventWriter<ConsoleLogRecord>,
+) {
+    buffer.drain().into_iter()
+        .for_each(|it| console_log_records.send(it));
+}
+
+
+/// Helpers
+/// Replace the directive for `module` (or the default level) within comma-separated filter directives
+fn with_level(directives: &str, level: &str, module: Option<&str>) -> String {
+    let is_replaced = |directive: &str| match (directive.split_once('='), module) {
+        (Some((target, _)), Some(module)) => target == module,
+        (None, None) => true,
+        _default => false,
+    };
+
+    let new_directive = match module {
+        Some(module) => format!("{}={}", module, level),
+        None => level.to_string(),
+    };
+
+    let directives: Vec<&str> = directives.split(',')
+        .map(str::trim)
+        .filter(|it| !it.is_empty() && !is_replaced(it))
+        .collect();
+
+    // The default level comes first, by convention
+    match module {
+        Some(_) => directives.into_iter().map(str::to_string).chain(std::iter::once(new_directive))
+            .collect::<Vec<String>>(),
+        None => std::iter::once(new_directive).chain(directives.into_iter().map(str::to_string))
+            .collect::<Vec<String>>(),
+    }.join(",")
+}
+
+
+#[cfg(test)]
+mod tests {
+    use chrono::Local;
+    use tracing::Level;
+
+    use super::{ConsoleLogBuffer, ConsoleLogRecord, MAX_BUFFERED_RECORDS, with_level};
+
+    #[test]
+    fn levels_are_replaced_per_module() {
+        assert_eq!(with_level("info,wgpu=error", "debug", None), "debug,wgpu=error");
+        assert_eq!(with_level("info,wgpu=error", "warn", Some("wgpu")), "info,wgpu=warn");
+        assert_eq!(
+            with_level("info,wgpu=error", "trace", Some("game_plugin::plugins::input")),
+            "info,wgpu=error,game_plugin::plugins::input=trace"
+        );
+        assert_eq!(with_level("wgpu=error", "off", None), "off,wgpu=error");
+    }
+
+    #[test]
+    fn buffer_drops_the_oldest_records() {
+        let buffer = ConsoleLogBuffer::default();
+        (0..MAX_BUFFERED_RECORDS + 10).for_each(|index| buffer.push(ConsoleLogRecord {
+            level: Level::INFO,
+            target: "test".to_string(),
+            message: index.to_string(),
+            timestamp: Local::now(),
+        }));
+
+        let records = buffer.drain();
+        assert_eq!(records.len(), MAX_BUFFERED_RECORDS);
+        assert_eq!(records[0].message, "10");
+        assert!(buffer.drain().is_empty());
+    }
+}
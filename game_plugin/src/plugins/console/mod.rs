@@ -0,0 +1,705 @@
+/// Developer console: a log of messages, and a command line.
+pub mod commands;
+pub mod completion;
+pub mod cvars;
+pub mod entities;
+pub mod history;
+pub mod input_line;
+pub mod logging;
+pub mod parser;
+#[cfg(feature = "remote_console")]
+pub mod remote;
+pub mod scripting;
+pub mod scrollback;
+
+use bevy::prelude::*;
+use bevy::app::Events;
+use bevy::input::mouse::{MouseScrollUnit, MouseWheel};
+
+use crate::GameState;
+use crate::plugins::actions;
+use crate::plugins::input;
+use crate::plugins::input::{InputContext, InputContexts};
+use self::commands::{AddConsoleCommand, ArgumentKind, CommandResult, ConsoleCommand, ConsoleCommands};
+use self::completion::{CompletionCandidates, CompletionSources};
+use self::cvars::{AddCvar, Cvar, CvarChanged, CvarValue, Cvars};
+use self::history::{CommandHistory, ReverseSearch};
+use self::input_line::{CARET, ConsoleInputLine};
+use self::logging::ConsoleLogRecord;
+use self::scrollback::{ConsoleScrollback, ScrollbackLayout};
+
+
+/// Rows scrolled per line of the mouse wheel
+const WHEEL_ROWS_PER_LINE: f32 = 3.0;
+
+
+/// Events
+pub struct LogToConsole(pub String);
+pub struct HandleConsoleCommand(pub String);
+pub struct RenderConsoleCommand(pub String);
+
+
+/// Labels to uniquely identify the Console Window
+///     (e.g. `With<ConsoleWindow>`) and its child widgets
+struct ConsoleWindow;
+struct ConsoleHistoryBox;
+struct ConsoleHistoryText;
+struct ConsoleTextInputBox;
+struct ConsoleTextInput;
+struct ConsoleCompletionBox;
+struct ConsoleCompletionText;
+
+
+
+/// Plugins
+pub struct ConsolePlugin;
+
+impl Plugin for ConsolePlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        let (log_filter, log_buffer) = logging::setup_logging();
+
+        app
+            .add_event::<LogToConsole>()
+            .add_event::<RenderConsoleCommand>()
+            .add_event::<HandleConsoleCommand>()
+            .init_resource::<ConsoleInputLine>()
+            .insert_resource(CommandHistory::load())
+            .init_resource::<ReverseSearch>()
+            .init_resource::<CompletionCandidates>()
+            .add_console_command(ConsoleCommand::new("help", "List the commands, or describe one of them")
+                .optional_argument("command", ArgumentKind::Command)
+                .run(commands::help))
+            .add_console_command(ConsoleCommand::new("exit", "Exit the game")
+                .alias("quit")
+                .send_event(|_| Ok(bevy::app::AppExit)))
+            .add_system(handle_console_command.exclusive_system())
+
+            // Entities
+            .add_console_command(ConsoleCommand::new("entities",
+                "List the entities, or those with a marker: cube, spectator or possessed")
+                .alias("ents")
+                .optional_argument("filter", ArgumentKind::String)
+                .run(entities::list))
+            .add_console_command(ConsoleCommand::new("count", "Count the entities, in total and per marker")
+                .optional_argument("filter", ArgumentKind::String)
+                .run(entities::count))
+            .add_console_command(ConsoleCommand::new("inspect",
+                "Show an entity's Transform, velocity and components, e.g. `inspect 3v0`")
+                .argument("entity", ArgumentKind::Entity)
+                .run(entities::inspect))
+            .add_console_command(ConsoleCommand::new("despawn",
+                "Despawn an entity, or every entity with a marker, e.g. `despawn 3v0` or `despawn cube`")
+                .argument("target", ArgumentKind::String)
+                .run(entities::despawn))
+            .add_console_command(ConsoleCommand::new("teleport",
+                "Move the Possessed camera to a position, or in front of an entity, e.g. `teleport 0 10 0` or `teleport 3v0`")
+                .alias("tp")
+                .variadic_argument("target", ArgumentKind::String)
+                .run(entities::teleport))
+
+            // Logs
+            .add_event::<ConsoleLogRecord>()
+            .init_resource::<ConsoleScrollback>()
+            .insert_resource(log_filter)
+            .insert_resource(log_buffer)
+            .add_console_command(ConsoleCommand::new("loglevel",
+                "Show the log filter, or set the level of every module (or of one), e.g. `loglevel debug wgpu`")
+                .optional_argument("level", ArgumentKind::String)
+                .optional_argument("module", ArgumentKind::String)
+                .run(logging::loglevel))
+
+            // Console variables
+            .add_event::<CvarChanged>()
+            .add_console_command(ConsoleCommand::new("set", "Set a cvar, e.g. `set cam_speed 30`")
+                .argument("cvar", ArgumentKind::Cvar)
+                .variadic_argument("value", ArgumentKind::String)
+                .run(cvars::set))
+            .add_console_command(ConsoleCommand::new("get", "Show the value of a cvar")
+                .argument("cvar", ArgumentKind::Cvar)
+                .run(cvars::get))
+            .add_console_command(ConsoleCommand::new("reset", "Reset a cvar to its default value")
+                .argument("cvar", ArgumentKind::Cvar)
+                .run(cvars::reset))
+            .add_console_command(ConsoleCommand::new("cvars", "List every cvar, with its value and description")
+                .run(cvars::list))
+            .add_cvar(Cvar::new("clear_color", CvarValue::Color(Color::rgb(0.2, 0.4, 0.4)),
+                "Background color of the game"))
+            .add_system(cvars::apply_clear_color.system())
+
+            // Scripting
+            .init_resource::<scripting::ConsoleAliases>()
+            .add_console_command(ConsoleCommand::new("exec",
+                "Run a script of commands, from the user configuration directory or from assets/")
+                .argument("path", ArgumentKind::String)
+                .run(scripting::exec))
+            .add_console_command(ConsoleCommand::new("alias",
+                "List the aliases, show one, or define one, e.g. `alias sc \"spawn cube\"`")
+                .optional_argument("name", ArgumentKind::String)
+                .variadic_argument("commands", ArgumentKind::String)
+                .run(scripting::alias))
+            .add_console_command(ConsoleCommand::new("unalias", "Remove an alias")
+                .argument("name", ArgumentKind::String)
+                .run(scripting::unalias))
+            .add_system_set(SystemSet::on_enter(GameState::Playing)
+                .with_system(on_enter.system())
+                .with_system(scripting::run_autoexec.exclusive_system()))
+            .add_system_set(SystemSet::on_update(GameState::Playing)
+                .with_system(handle_toggle_console.system())
+                .with_system(handle_key_inputs.system().label("handle_console_key_inputs"))
+                .with_system(render_input_line.system().after("handle_console_key_inputs"))
+                .with_system(render_completion_candidates.system().after("handle_console_key_inputs"))
+                .with_system(logging::drain_log_buffer.system().before("log_to_console"))
+                .with_system(log_to_console.system().label("log_to_console"))
+                .with_system(scroll_console.system().after("log_to_console").label("scroll_console"))
+                .with_system(render_scrollback.system().after("scroll_console")));
+
+        // Remote console, for automation (e.g. `cargo run --features native,remote_console`)
+        #[cfg(feature = "remote_console")]
+        app.add_plugin(remote::RemoteConsolePlugin);
+    }
+}
+
+
+
+/// Systems
+
+fn on_enter(
+    mut commands: Commands,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    asset_server: Res<AssetServer>
+) {
+    const FONT_ASSET_PATH: &str = "fonts/FiraSans-Bold.ttf";
+    let font: Handle<Font> = asset_server.load(FONT_ASSET_PATH);
+
+    // Init Materials
+    let transparent: Handle<ColorMaterial> = materials.add(Color::NONE.into());
+    let dark_gray: Handle<ColorMaterial> = materials.add(Color::DARK_GRAY.into());
+    let gray: Handle<ColorMaterial> = materials.add(Color::GRAY.into());
+
+    // Create Console window
+    commands
+        .spawn_bundle(create_console_window(transparent))
+            .insert(ConsoleWindow)
+
+            // Create User Input widget
+            .with_children(|parent| {
+                parent.spawn_bundle(create_text_input(dark_gray.clone()))
+                    .insert(ConsoleWindow)
+                    .insert(ConsoleTextInputBox)
+                    .with_children(|parent| {
+                        parent.spawn_bundle(create_text_input_box(font.clone()))
+                            .insert(ConsoleWindow)
+                            .insert(ConsoleTextInputBox)
+                            .insert(ConsoleTextInput);
+                    });
+            })
+
+            // Create Completion Candidates popup, hidden until a Tab matches several candidates
+            .with_children(|parent| {
+                parent.spawn_bundle(create_completion_box(dark_gray))
+                    .insert(ConsoleWindow)
+                    .insert(ConsoleCompletionBox)
+                    .with_children(|parent| {
+                        parent.spawn_bundle(TextBundle {
+                            visible: Visible { is_visible: false, ..Default::default() },
+                            ..create_log_line(&font, "".to_string(), Color::WHITE)
+                        })
+                            .insert(ConsoleWindow)
+                            .insert(ConsoleCompletionText);
+                    });
+            })
+
+            // Create Log History widget
+            .with_children(|parent| {
+                parent.spawn_bundle(create_log_panel(gray))
+                    .insert(ConsoleWindow)
+                    .insert(ConsoleHistoryBox)
+                    .with_children(|parent| {
+                        parent.spawn_bundle(TextBundle {
+                            visible: Visible { is_visible: false, ..Default::default() },
+                            ..create_log_line(&font, "".to_string(), Color::WHITE)
+                        })
+                            .insert(ConsoleWindow)
+                            .insert(ConsoleHistoryText);
+                    });
+            });
+}
+
+
+/// Toggle Console UI visibility
+fn handle_toggle_console(
+    mut toggle_console: EventReader<actions::ToggleConsole>,
+    mut console_window: Query<&mut Visible, With<ConsoleWindow>>,
+    mut input_contexts: ResMut<InputContexts>,
+) {
+    // Toggle visibility for the Console UI
+    toggle_console.iter().for_each(|_| {
+        let mut is_visible = false;
+
+        console_window.iter_mut().for_each(|mut window| {
+            is_visible = !window.is_visible;
+            window.is_visible = is_visible;
+        });
+
+        // Block/Unblock Input consumption for the gameplay contexts
+        if is_visible {
+            input_contexts.push(InputContext::Console);
+        } else {
+            input_contexts.pop(InputContext::Console);
+        }
+    });
+}
+
+
+/// Edit the console's command line: typed characters, caret movement and deletion, history and completion.
+///     Enter submits it.
+#[allow(clippy::too_many_arguments)]
+fn handle_key_inputs(
+    keys: Res<Input<KeyCode>>,
+    mut received_characters: EventReader<ReceivedCharacter>,
+    input_bindings: Res<input::InputBindings>,
+    mut process_console_command: EventWriter<HandleConsoleCommand>,
+    mut input_line: ResMut<ConsoleInputLine>,
+    mut history: ResMut<CommandHistory>,
+    mut reverse_search: ResMut<ReverseSearch>,
+    mut completion_candidates: ResMut<CompletionCandidates>,
+    console_commands: Res<ConsoleCommands>,
+    cvars: Option<Res<Cvars>>,
+    input_contexts: Res<InputContexts>,
+) {
+    // Always read the characters, so that those typed while playing do not show up when the console opens
+    let characters: Vec<char> = received_characters.iter().map(|it| it.char).collect();
+    if !input_contexts.is_focused(InputContext::Console) {
+        return;
+    }
+
+    const DEFAULT_CONSOLE_KEY: KeyCode = KeyCode::Grave;
+    let mut console_keys: Vec<KeyCode> = input_bindings
+        .keys_for(actions::GameActionBinding::ToggleConsole)
+        .collect();
+    if console_keys.is_empty() {
+        console_keys.push(DEFAULT_CONSOLE_KEY);
+    }
+
+    // The key toggling the console also types a character, which does not belong in the command line.
+    //     Neither do Ctrl shortcuts, although AltGr (reported as Ctrl+Alt on Windows) types characters.
+    let is_toggling_console = console_keys.iter().any(|&it| keys.just_pressed(it));
+    let is_ctrl_pressed = keys.pressed(KeyCode::LControl) || keys.pressed(KeyCode::RControl);
+    let is_alt_pressed = keys.pressed(KeyCode::LAlt) || keys.pressed(KeyCode::RAlt);
+    let characters: Vec<char> = characters.into_iter()
+        .filter(|it| !is_toggling_console && !it.is_control())
+        .filter(|_| !is_ctrl_pressed || is_alt_pressed)
+        .collect();
+    let just_pressed: Vec<KeyCode> = keys.get_just_pressed().copied().collect();
+
+    if reverse_search.is_active {
+        handle_reverse_search_keys(&characters, &just_pressed, is_ctrl_pressed,
+            &mut input_line, &mut history, &mut reverse_search, &mut process_console_command);
+        return;
+    }
+
+    if !characters.is_empty() {
+        characters.iter().for_each(|&it| input_line.insert(it));
+        history.reset_navigation();
+        completion_candidates.0.clear();
+    }
+
+    just_pressed.iter().for_each(|key: &KeyCode| match key {
+        KeyCode::Left => input_line.move_left(),
+        KeyCode::Right => input_line.move_right(),
+        KeyCode::Home => input_line.move_home(),
+        KeyCode::End => input_line.move_end(),
+        KeyCode::Back if is_ctrl_pressed => input_line.delete_word_back(),
+        KeyCode::Back => input_line.delete_back(),
+        KeyCode::Delete => input_line.delete_forward(),
+        KeyCode::Up => {
+            let current = input_line.text().to_string();
+            if let Some(entry) = history.previous(&current) {
+                input_line.set_text(entry);
+            }
+        },
+        KeyCode::Down => {
+            if let Some(entry) = history.next() {
+                input_line.set_text(entry);
+            }
+        },
+        KeyCode::R if is_ctrl_pressed => {
+            reverse_search.start(input_line.text());
+            completion_candidates.0.clear();
+        },
+        KeyCode::Tab => {
+            let sources = CompletionSources { commands: &console_commands, cvars: cvars.as_deref() };
+            let completion = sources.complete(input_line.split_at_caret().0);
+            if let Some(replacement) = completion.replacement() {
+                input_line.replace_before_caret(completion.start, &replacement);
+            }
+            completion_candidates.0 = match completion.candidates.len() {
+                0 | 1 => Vec::new(),
+                _default => completion.candidates,
+            };
+        },
+        // Queue the contents for processing
+        KeyCode::Return | KeyCode::NumpadEnter if !input_line.is_empty() => {
+            submit(input_line.take(), &mut history, &mut process_console_command);
+            completion_candidates.0.clear();
+        },
+        _default => {},
+    });
+}
+
+/// While searching the history with Ctrl+R: type to refine the search, Ctrl+R for an older match,
+///     Enter to run the match, arrows to edit it, and Escape (or Ctrl+G) to cancel.
+fn handle_reverse_search_keys(
+    characters: &[char],
+    just_pressed: &[KeyCode],
+    is_ctrl_pressed: bool,
+    input_line: &mut ConsoleInputLine,
+    history: &mut CommandHistory,
+    reverse_search: &mut ReverseSearch,
+    process_console_command: &mut EventWriter<HandleConsoleCommand>,
+) {
+    if !characters.is_empty() {
+        reverse_search.query.extend(characters);
+        reverse_search.update(history);
+    }
+
+    let found = |reverse_search: &ReverseSearch, history: &CommandHistory| reverse_search.found
+        .and_then(|it| history.get(it))
+        .map(str::to_string);
+
+    just_pressed.iter().for_each(|key: &KeyCode| match key {
+        KeyCode::R if is_ctrl_pressed => reverse_search.search_older(history),
+        KeyCode::Back => {
+            reverse_search.query.pop();
+            reverse_search.update(history);
+        },
+        KeyCode::G if !is_ctrl_pressed => {},
+        KeyCode::Escape | KeyCode::G => {
+            input_line.set_text(&reverse_search.original_line);
+            reverse_search.stop();
+        },
+        KeyCode::Return | KeyCode::NumpadEnter => {
+            if let Some(line) = found(reverse_search, history) {
+                input_line.take();
+                submit(line, history, process_console_command);
+            }
+            reverse_search.stop();
+        },
+        KeyCode::Left | KeyCode::Right | KeyCode::Home | KeyCode::End | KeyCode::Up | KeyCode::Down => {
+            let line = found(reverse_search, history)
+                .unwrap_or_else(|| reverse_search.original_line.clone());
+            input_line.set_text(&line);
+            reverse_search.stop();
+        },
+        _default => {},
+    });
+}
+
+/// Render the command line, with the caret between the text before and after it.
+///     While searching the history, render the search prompt instead.
+fn render_input_line(
+    input_line: Res<ConsoleInputLine>,
+    history: Res<CommandHistory>,
+    reverse_search: Res<ReverseSearch>,
+    mut console_text_input: Query<&mut Text, With<ConsoleTextInput>>,
+) {
+    if !input_line.is_changed() && !reverse_search.is_changed() {
+        return;
+    }
+
+    let prompt = reverse_search.prompt(&history);
+    let (before_caret, after_caret) = if reverse_search.is_active {
+        (prompt.as_str(), "")
+    } else {
+        input_line.split_at_caret()
+    };
+    console_text_input.iter_mut().for_each(|mut text_input| {
+        text_input.sections[0].value = before_caret.to_string();
+        text_input.sections[2].value = after_caret.to_string();
+    });
+}
+
+/// Show the completion candidates below the command line, hiding the popup when there are none
+fn render_completion_candidates(
+    completion_candidates: Res<CompletionCandidates>,
+    mut completion_box: Query<&mut Style, With<ConsoleCompletionBox>>,
+    mut completion_text: Query<&mut Text, With<ConsoleCompletionText>>,
+) {
+    if !completion_candidates.is_changed() {
+        return;
+    }
+
+    let display = if completion_candidates.0.is_empty() { Display::None } else { Display::Flex };
+    completion_box.iter_mut().for_each(|mut style| style.display = display);
+    completion_text.iter_mut().for_each(|mut text| {
+        text.sections[0].value = completion_candidates.0.join("    ");
+    });
+}
+
+/// Remember the command, then queue it for processing
+fn submit(
+    line: String,
+    history: &mut CommandHistory,
+    process_console_command: &mut EventWriter<HandleConsoleCommand>,
+) {
+    history.push(&line);
+    if let Err(error) = history.save() {
+        error!("{}", error);
+    }
+    process_console_command.send(HandleConsoleCommand(line));
+}
+
+/// Business Logic side effects of entering a console command: run it through the ConsoleCommands registry.
+///     An exclusive system, since command handlers get access to the whole World.
+fn handle_console_command(world: &mut World) {
+    let lines: Vec<String> = match world.get_resource_mut::<Events<HandleConsoleCommand>>() {
+        Some(mut events) => events.drain().map(|it| it.0).collect(),
+        None => return,
+    };
+
+    lines.iter().for_each(|line| {
+        run_console_command(world, line);
+    });
+}
+
+/// Run a line of commands as if it was typed in the console, echoing it and its output to the Console history
+pub fn run_console_command(world: &mut World, line: &str) -> Vec<CommandResult> {
+    let results = scripting::execute(world, line);
+
+    let mut messages = vec![format!("> {}", line)];
+    results.iter().for_each(|result| match result {
+        Ok(message) if message.is_empty() => {},
+        Ok(message) => messages.push(message.clone()),
+        Err(error) => messages.push(error.to_string()),
+    });
+
+    if let Some(mut log_to_console) = world.get_resource_mut::<Events<LogToConsole>>() {
+        messages.into_iter().for_each(|it| log_to_console.send(LogToConsole(it)));
+    }
+    results
+}
+
+
+/// Side effects of entering a console command, or of logging: add the messages to the Console history
+fn log_to_console(
+    mut log_to_console: EventReader<LogToConsole>,
+    mut console_log_records: EventReader<ConsoleLogRecord>,
+    mut scrollback: ResMut<ConsoleScrollback>,
+) {
+    log_to_console.iter()
+        .for_each(|it| scrollback.push(&it.0, Color::WHITE));
+    console_log_records.iter()
+        .for_each(|it| scrollback.push(&it.to_string(), it.color()));
+}
+
+/// Scroll the Console history with the mouse wheel, PageUp and PageDown (or Ctrl+End, back to the bottom)
+fn scroll_console(
+    keys: Res<Input<KeyCode>>,
+    mut mouse_wheel: EventReader<MouseWheel>,
+    windows: Res<Windows>,
+    input_contexts: Res<InputContexts>,
+    mut scrollback: ResMut<ConsoleScrollback>,
+) {
+    // Always read the events, so that older ones do not scroll the console when it opens
+    let wheel_rows: f32 = mouse_wheel.iter()
+        .map(|it| match it.unit {
+            MouseScrollUnit::Line => it.y * WHEEL_ROWS_PER_LINE,
+            MouseScrollUnit::Pixel => it.y / get_font_size(),
+        })
+        .sum();
+    if !input_contexts.is_focused(InputContext::Console) {
+        return;
+    }
+
+    let layout = get_scrollback_layout(&windows);
+    let page = layout.rows.saturating_sub(1).max(1);
+    let is_ctrl_pressed = keys.pressed(KeyCode::LControl) || keys.pressed(KeyCode::RControl);
+
+    if wheel_rows > 0.0 {
+        scrollback.scroll_up(wheel_rows.round() as usize, layout);
+    } else if wheel_rows < 0.0 {
+        scrollback.scroll_down((-wheel_rows).round() as usize);
+    }
+    if keys.just_pressed(KeyCode::PageUp) {
+        scrollback.scroll_up(page, layout);
+    }
+    if keys.just_pressed(KeyCode::PageDown) {
+        scrollback.scroll_down(page);
+    }
+    if is_ctrl_pressed && keys.just_pressed(KeyCode::End) {
+        scrollback.scroll_to_bottom();
+    }
+}
+
+/// Render the rows of the Console history in view, whenever they (or the window size) change
+fn render_scrollback(
+    scrollback: Res<ConsoleScrollback>,
+    windows: Res<Windows>,
+    mut last_layout: Local<Option<ScrollbackLayout>>,
+    mut console_history_text: Query<&mut Text, With<ConsoleHistoryText>>,
+) {
+    let layout = get_scrollback_layout(&windows);
+    if !scrollback.is_changed() && *last_layout == Some(layout) {
+        return;
+    }
+    *last_layout = Some(layout);
+
+    let rows = scrollback.visible_rows(layout);
+    console_history_text.iter_mut().for_each(|mut text| {
+        let style = text.sections[0].style.clone();
+        text.sections = rows.iter()
+            .enumerate()
+            .map(|(index, row)| TextSection {
+                value: if index + 1 < rows.len() { format!("{}\n", row.text) } else { row.text.clone() },
+                style: TextStyle { color: row.color, ..style.clone() },
+            })
+            .collect();
+
+        // Keep a section around, for its style
+        if text.sections.is_empty() {
+            text.sections.push(TextSection { value: "".to_string(), style });
+        }
+    });
+}
+
+
+/// Helpers
+fn get_font_size() -> f32 {
+    TextStyle::default().font_size
+}
+
+/// The Console history panel takes half of the window, minus the input line and the paddings
+fn get_scrollback_layout(windows: &Windows) -> ScrollbackLayout {
+    let (width, height) = windows.get_primary()
+        .map_or((800.0, 600.0), |it| (it.width(), it.height()));
+    ScrollbackLayout::fit(width - 8.0, height * 0.5 - 25.0 - 8.0, get_font_size())
+}
+
+
+
+/// Widget Factory methods
+
+fn create_console_window(background_color: Handle<ColorMaterial>) -> NodeBundle {
+    NodeBundle {
+        style: Style {
+            position_type: PositionType::Absolute,
+            position: Rect { top: Val::Px(0.0), left: Val::Px(0.0), ..Default::default() },
+            size: Size::new(Val::Percent(100.0), Val::Percent(50.0)),
+            justify_content: JustifyContent::FlexStart,
+            flex_direction: FlexDirection::Column,
+            ..Default::default()
+        },
+        material: background_color,
+        visible: Visible { is_visible: false, ..Default::default() },
+        ..Default::default()
+    }
+}
+
+fn create_text_input(background_color: Handle<ColorMaterial>) -> NodeBundle {
+    NodeBundle {
+        style: Style {
+            position_type: PositionType::Relative,
+            position: Rect { top: Val::Px(0.0), ..Default::default() },
+            size: Size::new(Val::Percent(100.0), Val::Px(25.0)),
+            flex_direction: FlexDirection::Row,
+            justify_content: JustifyContent::FlexStart,
+            align_items: AlignItems::Center,
+            padding: Rect {
+                top: Val::Px(2.0),
+                bottom: Val::Px(2.0),
+                left: Val::Px(8.0),
+                right: Val::Px(8.0)
+            },
+            ..Default::default()
+        },
+        material: background_color,
+        visible: Visible { is_visible: false, ..Default::default() },
+        ..Default::default()
+    }
+}
+
+/// Three sections: the text before the caret, the caret itself, and the text after it
+fn create_text_input_box(font: Handle<Font>) -> TextBundle {
+    let style = TextStyle {
+        font,
+        ..Default::default()
+    };
+    let caret_style = TextStyle {
+        color: Color::YELLOW,
+        ..style.clone()
+    };
+
+    TextBundle {
+        text: Text {
+            sections: vec![
+                TextSection { value: "".to_string(), style: style.clone() },
+                TextSection { value: CARET.to_string(), style: caret_style },
+                TextSection { value: "".to_string(), style },
+            ],
+            ..Default::default()
+        },
+        visible: Visible { is_visible: false, ..Default::default() },
+        ..Default::default()
+    }
+}
+
+fn create_completion_box(background_color: Handle<ColorMaterial>) -> NodeBundle {
+    NodeBundle {
+        style: Style {
+            display: Display::None,
+            position_type: PositionType::Relative,
+            size: Size::new(Val::Percent(100.0), Val::Auto),
+            flex_direction: FlexDirection::Row,
+            flex_wrap: FlexWrap::Wrap,
+            padding: Rect {
+                top: Val::Px(2.0),
+                bottom: Val::Px(2.0),
+                left: Val::Px(8.0),
+                right: Val::Px(8.0)
+            },
+            ..Default::default()
+        },
+        material: background_color,
+        visible: Visible { is_visible: false, ..Default::default() },
+        ..Default::default()
+    }
+}
+
+fn create_log_panel(background_color: Handle<ColorMaterial>) -> NodeBundle {
+    NodeBundle {
+        style: Style {
+            position_type: PositionType::Relative,
+            position: Rect { top: Val::Px(0.0), ..Default::default() },
+            size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+            flex_direction: FlexDirection::ColumnReverse,
+            justify_content: JustifyContent::FlexStart,
+            align_items: AlignItems::FlexStart,
+            padding: Rect::all(Val::Px(4.0)),
+            ..Default::default()
+        },
+        material: background_color,
+        visible: Visible { is_visible: false, ..Default::default() },
+        ..Default::default()
+    }
+}
+
+fn create_log_line(font: &Handle<Font>, message: String, color: Color) -> TextBundle {
+    TextBundle {
+        text: Text {
+            sections: vec![
+                TextSection {
+                    value: message,
+                    style: TextStyle {
+                        font: font.as_weak(),
+                        color,
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                }
+            ],
+            ..Default::default()
+        },
+        visible: Visible { is_visible: true, ..Default::default() },
+        ..Default::default()
+    }
+}
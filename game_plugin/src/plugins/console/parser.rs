@@ -0,0 +1,456 @@
+/// Console command parser: split a line into a command name and typed arguments.
+///     e.g. `spawn cube 0 10 0 color=red` or `say "hello world"`
+use std::error::Error;
+use std::fmt;
+
+use bevy::prelude::*;
+
+
+/// A typed argument value, inferred from its text unless it is quoted
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    /// `true`, `false`, `on`, `off`
+    Bool(bool),
+    /// `42`, `-3`, with the text it was read from
+    Integer(i64, String),
+    /// `0.5`, `-1e3`, with the text it was read from
+    Float(f32, String),
+    /// `1,2,3`: comma-separated numbers, without spaces
+    Vector(Vec<f32>),
+    /// `42v0`: an entity id and generation, as Bevy prints them
+    Entity(Entity),
+    /// Anything else, or any quoted text
+    String(String),
+}
+
+impl Value {
+    /// Infer the type of an unquoted text
+    pub fn parse(text: &str) -> Value {
+        match text.to_lowercase().as_str() {
+            "true" | "on" => return Value::Bool(true),
+            "false" | "off" => return Value::Bool(false),
+            _default => {},
+        }
+
+        if let Ok(value) = text.parse::<i64>() {
+            return Value::Integer(value, text.to_string());
+        }
+        if let Ok(value) = text.parse::<f32>() {
+            if value.is_finite() {
+                return Value::Float(value, text.to_string());
+            }
+        }
+        if text.contains(',') {
+            let components: Result<Vec<f32>, _> = text.split(',').map(str::parse::<f32>).collect();
+            if let Ok(components) = components {
+                return Value::Vector(components);
+            }
+        }
+        if let Some(entity) = parse_entity(text) {
+            return Value::Entity(entity);
+        }
+
+        Value::String(text.to_string())
+    }
+
+    /// Name of the value's type, for error messages
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::Bool(_) => "boolean",
+            Value::Integer(..) => "integer",
+            Value::Float(..) => "number",
+            Value::Vector(_) => "vector",
+            Value::Entity(_) => "entity",
+            Value::String(_) => "string",
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Value::Bool(value) => Some(*value),
+            Value::Integer(value, _) => Some(*value != 0),
+            _default => None,
+        }
+    }
+
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            Value::Integer(value, _) => Some(*value),
+            _default => None,
+        }
+    }
+
+    /// Integers are accepted wherever a number is expected
+    pub fn as_f32(&self) -> Option<f32> {
+        match self {
+            Value::Integer(value, _) => Some(*value as f32),
+            Value::Float(value, _) => Some(*value),
+            _default => None,
+        }
+    }
+
+    pub fn as_vec3(&self) -> Option<Vec3> {
+        match self {
+            Value::Vector(components) if components.len() == 3 =>
+                Some(Vec3::new(components[0], components[1], components[2])),
+            _default => None,
+        }
+    }
+
+    pub fn as_entity(&self) -> Option<Entity> {
+        match self {
+            Value::Entity(entity) => Some(*entity),
+            _default => None,
+        }
+    }
+
+    /// A color name (`red`), or a hex code (`#ff8000`, `ff8000cc`)
+    pub fn as_color(&self) -> Option<Color> {
+        let text = match self {
+            Value::String(text) => text.to_lowercase(),
+            // Hex codes like `008000` or `00e000` are read as numbers: use them as they were typed
+            Value::Integer(_, text) | Value::Float(_, text) => text.to_lowercase(),
+            _default => return None,
+        };
+
+        match text.as_str() {
+            "white" => Some(Color::WHITE),
+            "black" => Some(Color::BLACK),
+            "gray" | "grey" => Some(Color::GRAY),
+            "red" => Some(Color::RED),
+            "green" => Some(Color::GREEN),
+            "blue" => Some(Color::BLUE),
+            "yellow" => Some(Color::YELLOW),
+            "cyan" => Some(Color::CYAN),
+            "purple" => Some(Color::PURPLE),
+            "pink" => Some(Color::PINK),
+            "orange" => Some(Color::ORANGE),
+            _default => Color::hex(text.trim_start_matches('#')).ok(),
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Bool(value) => write!(f, "{}", value),
+            Value::Integer(_, text) => write!(f, "{}", text),
+            Value::Float(_, text) => write!(f, "{}", text),
+            Value::Vector(components) => {
+                let components: Vec<String> = components.iter().map(f32::to_string).collect();
+                write!(f, "{}", components.join(","))
+            },
+            Value::Entity(entity) => write!(f, "{}v{}", entity.id(), entity.generation()),
+            Value::String(value) => write!(f, "{}", value),
+        }
+    }
+}
+
+
+/// A parsed console command line
+#[derive(Debug, Clone, PartialEq)]
+pub struct CommandLine {
+    /// Lower-cased: command names are case-insensitive
+    pub name: String,
+    pub arguments: Vec<Value>,
+    /// `name=value` arguments, in the order they were given
+    pub named_arguments: Vec<(String, Value)>,
+}
+
+impl CommandLine {
+    pub fn argument(&self, index: usize) -> Option<&Value> {
+        self.arguments.get(index)
+    }
+
+    pub fn named_argument(&self, name: &str) -> Option<&Value> {
+        self.named_arguments.iter()
+            .rev()
+            .find(|(it, _)| it == name)
+            .map(|(_, value)| value)
+    }
+
+    /// Read a required positional argument, e.g. `command.require(0, "a number", Value::as_f32)`
+    pub fn require<T>(
+        &self,
+        index: usize,
+        expected: &'static str,
+        read: impl Fn(&Value) -> Option<T>,
+    ) -> Result<T, ArgumentError> {
+        let value = self.argument(index)
+            .ok_or(ArgumentError::Missing { index, expected })?;
+        read(value).ok_or_else(|| ArgumentError::WrongType {
+            argument: format!("#{}", index + 1),
+            expected,
+            found: value.clone(),
+        })
+    }
+
+    /// Read an optional named argument; it is an error only if it is present with the wrong type
+    pub fn optional<T>(
+        &self,
+        name: &str,
+        expected: &'static str,
+        read: impl Fn(&Value) -> Option<T>,
+    ) -> Result<Option<T>, ArgumentError> {
+        self.named_argument(name)
+            .map(|value| read(value).ok_or_else(|| ArgumentError::WrongType {
+                argument: name.to_string(),
+                expected,
+                found: value.clone(),
+            }))
+            .transpose()
+    }
+
+    /// A position, given either as a single vector (`0,10,0`) or as three numbers (`0 10 0`)
+    pub fn vec3_at(&self, index: usize) -> Result<Vec3, ArgumentError> {
+        if let Some(vector) = self.argument(index).and_then(Value::as_vec3) {
+            return Ok(vector);
+        }
+
+        let x = self.require(index, "a vector or 3 numbers", Value::as_f32)?;
+        let y = self.require(index + 1, "a number", Value::as_f32)?;
+        let z = self.require(index + 2, "a number", Value::as_f32)?;
+        Ok(Vec3::new(x, y, z))
+    }
+}
+
+
+/// Errors in the syntax of a command line. Columns start at 1.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParseError {
+    Empty,
+    UnterminatedQuote { column: usize },
+    MissingArgumentName { column: usize },
+    InvalidCommandName { name: String },
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::Empty => write!(f, "Empty command"),
+            ParseError::UnterminatedQuote { column } =>
+                write!(f, "Unterminated quote starting at column {}", column),
+            ParseError::MissingArgumentName { column } =>
+                write!(f, "Missing argument name before '=' at column {}", column),
+            ParseError::InvalidCommandName { name } =>
+                write!(f, "Invalid command name '{}': expected a word, not a value", name),
+        }
+    }
+}
+
+impl Error for ParseError {}
+
+
+/// Errors in the arguments given to a command
+#[derive(Debug, Clone, PartialEq)]
+pub enum ArgumentError {
+    Missing { index: usize, expected: &'static str },
+    WrongType { argument: String, expected: &'static str, found: Value },
+    TooMany { expected: usize, found: usize },
+    UnknownName { name: String },
+}
+
+impl fmt::Display for ArgumentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ArgumentError::Missing { index, expected } =>
+                write!(f, "Missing argument #{}: expected {}", index + 1, expected),
+            ArgumentError::WrongType { argument, expected, found } =>
+                write!(f, "Argument {}: expected {}, found {} '{}'", argument, expected, found.type_name(), found),
+            ArgumentError::TooMany { expected, found } =>
+                write!(f, "Too many arguments: expected at most {}, found {}", expected, found),
+            ArgumentError::UnknownName { name } =>
+                write!(f, "Unknown argument '{}='", name),
+        }
+    }
+}
+
+impl Error for ArgumentError {}
+
+
+/// A token, before its value is inferred
+struct Token {
+    name: Option<String>,
+    text: String,
+    is_quoted: bool,
+}
+
+/// Split a line on whitespace. Quotes (`"` or `'`) group words, and support `\` escapes.
+fn tokenize(line: &str) -> Result<Vec<Token>, ParseError> {
+    let mut tokens: Vec<Token> = Vec::new();
+    let mut chars = line.chars().enumerate().peekable();
+
+    while let Some(&(_, it)) = chars.peek() {
+        if it.is_whitespace() {
+            chars.next();
+            continue;
+        }
+
+        let mut token = Token { name: None, text: String::new(), is_quoted: false };
+        while let Some(&(position, it)) = chars.peek() {
+            if it.is_whitespace() {
+                break;
+            }
+            chars.next();
+
+            match it {
+                '"' | '\'' => {
+                    token.is_quoted = true;
+                    let mut is_terminated = false;
+                    while let Some((_, next)) = chars.next() {
+                        match next {
+                            _ if next == it => {
+                                is_terminated = true;
+                                break;
+                            },
+                            '\\' => match chars.next() {
+                                Some((_, 'n')) => token.text.push('\n'),
+                                Some((_, escaped)) => token.text.push(escaped),
+                                None => break,
+                            },
+                            _default => token.text.push(next),
+                        }
+                    }
+                    if !is_terminated {
+                        return Err(ParseError::UnterminatedQuote { column: position + 1 });
+                    }
+                },
+                '=' if token.name.is_none() && !token.is_quoted => {
+                    if token.text.is_empty() {
+                        return Err(ParseError::MissingArgumentName { column: position + 1 });
+                    }
+                    token.name = Some(std::mem::take(&mut token.text).to_lowercase());
+                },
+                _default => token.text.push(it),
+            }
+        }
+
+        tokens.push(token);
+    }
+
+    Ok(tokens)
+}
+
+/// Parse a console command line into its name and typed arguments
+pub fn parse_command_line(line: &str) -> Result<CommandLine, ParseError> {
+    let mut tokens = tokenize(line)?.into_iter();
+
+    let name = match tokens.next() {
+        None => return Err(ParseError::Empty),
+        Some(Token { name: None, text, is_quoted: false }) => text.to_lowercase(),
+        Some(Token { name, text, .. }) => return Err(ParseError::InvalidCommandName {
+            name: name.map_or(text.clone(), |name| format!("{}={}", name, text)),
+        }),
+    };
+
+    let mut command_line = CommandLine { name, arguments: Vec::new(), named_arguments: Vec::new() };
+    tokens.for_each(|token| {
+        let value = if token.is_quoted { Value::String(token.text) } else { Value::parse(&token.text) };
+        match token.name {
+            Some(name) => command_line.named_arguments.push((name, value)),
+            None => command_line.arguments.push(value),
+        }
+    });
+
+    Ok(command_line)
+}
+
+/// Split a chain of commands on `;`, except within quotes, e.g. `spawn cube; say "a; b"`.
+///     Blank commands are skipped.
+pub fn split_commands(line: &str) -> Vec<String> {
+    let mut commands: Vec<String> = Vec::new();
+    let mut command = String::new();
+    let mut quote: Option<char> = None;
+    let mut is_escaped = false;
+
+    line.chars().for_each(|it| {
+        match (quote, it) {
+            (Some(_), _) if is_escaped => is_escaped = false,
+            (Some(_), '\\') => is_escaped = true,
+            (Some(open), _) if it == open => quote = None,
+            (None, '"') | (None, '\'') => quote = Some(it),
+            (None, ';') => {
+                commands.push(std::mem::take(&mut command));
+                return;
+            },
+            _default => {},
+        }
+        command.push(it);
+    });
+    commands.push(command);
+
+    commands.into_iter()
+        .map(|it| it.trim().to_string())
+        .filter(|it| !it.is_empty())
+        .collect()
+}
+
+/// Helper: `42v0` into an Entity
+fn parse_entity(text: &str) -> Option<Entity> {
+    let mut parts = text.splitn(2, 'v');
+    let id: u32 = parts.next()?.parse().ok()?;
+    let generation: u32 = parts.next()?.parse().ok()?;
+    Some(Entity::from_bits((generation as u64) << 32 | id as u64))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+
+    use super::{ParseError, Value, parse_command_line, split_commands};
+
+    #[test]
+    fn arguments_are_typed() {
+        let command = parse_command_line("Spawn cube 0 10.5 on 1,2,3 3v1 'dark red'").unwrap();
+
+        assert_eq!(command.name, "spawn");
+        assert_eq!(command.arguments, vec![
+            Value::String("cube".to_string()),
+            Value::Integer(0, "0".to_string()),
+            Value::Float(10.5, "10.5".to_string()),
+            Value::Bool(true),
+            Value::Vector(vec![1.0, 2.0, 3.0]),
+            Value::Entity(Entity::from_bits(1 << 32 | 3)),
+            Value::String("dark red".to_string()),
+        ]);
+    }
+
+    #[test]
+    fn named_arguments_and_positions() {
+        let command = parse_command_line("spawn cube 0 10 0 color=red label=\"a \\\"b\\\"\"").unwrap();
+
+        assert_eq!(command.vec3_at(1).unwrap(), Vec3::new(0.0, 10.0, 0.0));
+        assert_eq!(command.named_argument("color"), Some(&Value::String("red".to_string())));
+        assert_eq!(command.named_argument("label"), Some(&Value::String("a \"b\"".to_string())));
+        assert!(command.vec3_at(2).is_err());
+    }
+
+    #[test]
+    fn colors_are_read_as_typed() {
+        assert_eq!(Value::parse("Red").as_color(), Some(Color::RED));
+        assert_eq!(Value::parse("#ff8000").as_color(), Some(Color::rgb_u8(255, 128, 0)));
+        // Hex codes that look like numbers
+        assert_eq!(Value::parse("008000").as_color(), Some(Color::rgb_u8(0, 128, 0)));
+        assert_eq!(Value::parse("00e000").as_color(), Some(Color::rgb_u8(0, 224, 0)));
+        assert_eq!(Value::parse("42").as_color(), None);
+        assert_eq!(Value::parse("1,2,3").as_color(), None);
+    }
+
+    #[test]
+    fn syntax_errors() {
+        assert_eq!(parse_command_line("   "), Err(ParseError::Empty));
+        assert_eq!(parse_command_line("say \"hello"), Err(ParseError::UnterminatedQuote { column: 5 }));
+        assert_eq!(parse_command_line("spawn =red"), Err(ParseError::MissingArgumentName { column: 7 }));
+        assert!(matches!(parse_command_line("\"spawn\""), Err(ParseError::InvalidCommandName { .. })));
+    }
+
+    #[test]
+    fn commands_are_chained_outside_quotes() {
+        assert_eq!(split_commands("spawn cube; spectator ;; "), vec!["spawn cube", "spectator"]);
+        assert_eq!(split_commands("alias setup \"spawn cube; spectator\"; setup"),
+            vec!["alias setup \"spawn cube; spectator\"", "setup"]);
+        assert_eq!(split_commands("say 'it\\'s; fine'"), vec!["say 'it\\'s; fine'"]);
+        assert!(split_commands("  ").is_empty());
+    }
+}
@@ -0,0 +1,351 @@
+/// Remote console: drive a running game without a keyboard, e.g. from a test harness.
+///     Listens on a localhost TCP port (or a Unix socket), one command line per request line; every request
+///     gets exactly one response line, `ok <output>` or `error <output>`, with newlines escaped as `\n`.
+///     The commands run as if typed in the console, so they are echoed to its history as well.
+///     Only built with the `remote_console` feature; the address is read from `GAME_REMOTE_CONSOLE`,
+///     e.g. `127.0.0.1:27015` (the default) or `unix:/tmp/bevy_game.sock`.
+use std::fmt;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener};
+#[cfg(unix)]
+use std::os::unix::fs::FileTypeExt;
+#[cfg(unix)]
+use std::os::unix::net::UnixListener;
+#[cfg(unix)]
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+
+use bevy::prelude::*;
+
+use crate::plugins::console::commands::CommandResult;
+use crate::plugins::console::run_console_command;
+
+
+/// Environment variable holding the address to listen on
+pub const ADDRESS_VARIABLE: &str = "GAME_REMOTE_CONSOLE";
+
+pub const DEFAULT_ADDRESS: &str = "127.0.0.1:27015";
+
+
+/// Plugins
+pub struct RemoteConsolePlugin;
+
+impl Plugin for RemoteConsolePlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        let address = std::env::var(ADDRESS_VARIABLE).unwrap_or_else(|_| DEFAULT_ADDRESS.to_string());
+        let remote_console = RemoteAddress::parse(&address)
+            .and_then(|it| RemoteConsole::listen(&it).map_err(|error| format!("Unable to listen on {}: {}", it, error)));
+
+        match remote_console {
+            Ok(remote_console) => {
+                info!("Remote console listening on {}", remote_console.address());
+                app
+                    .insert_resource(remote_console)
+                    .add_system(handle_remote_requests.exclusive_system());
+            },
+            Err(error) => error!("Remote console disabled: {}", error),
+        }
+    }
+}
+
+
+/// Where the remote console listens: never anything but the local machine
+#[derive(Debug, Clone, PartialEq)]
+pub enum RemoteAddress {
+    Tcp(SocketAddr),
+    #[cfg(unix)]
+    Unix(PathBuf),
+}
+
+impl RemoteAddress {
+    /// `127.0.0.1:27015`, `[::1]:27015` or `unix:/path/to/socket`
+    pub fn parse(text: &str) -> Result<RemoteAddress, String> {
+        #[cfg(unix)]
+        if let Some(path) = text.strip_prefix("unix:") {
+            return Ok(RemoteAddress::Unix(PathBuf::from(path)));
+        }
+
+        let address: SocketAddr = text.parse()
+            .map_err(|_| format!("Invalid address '{}', e.g. {} or unix:/tmp/game.sock", text, DEFAULT_ADDRESS))?;
+        if !address.ip().is_loopback() {
+            return Err(format!("Refusing to listen on {}: only loopback addresses are allowed", address));
+        }
+        Ok(RemoteAddress::Tcp(address))
+    }
+}
+
+impl fmt::Display for RemoteAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RemoteAddress::Tcp(address) => write!(f, "{}", address),
+            #[cfg(unix)]
+            RemoteAddress::Unix(path) => write!(f, "unix:{}", path.display()),
+        }
+    }
+}
+
+
+/// A command line received from a client, and where to send its response
+pub struct RemoteRequest {
+    pub line: String,
+    reply: Sender<RemoteResponse>,
+}
+
+/// The output of a request's commands, and whether they all succeeded
+#[derive(Debug, Clone, PartialEq)]
+pub struct RemoteResponse {
+    pub is_ok: bool,
+    pub message: String,
+}
+
+impl RemoteResponse {
+    pub fn from_results(results: &[CommandResult]) -> RemoteResponse {
+        let messages: Vec<String> = results.iter()
+            .map(|it| match it {
+                Ok(message) => message.clone(),
+                Err(error) => error.to_string(),
+            })
+            .filter(|it| !it.is_empty())
+            .collect();
+
+        RemoteResponse { is_ok: results.iter().all(Result::is_ok), message: messages.join("\n") }
+    }
+}
+
+impl fmt::Display for RemoteResponse {
+    /// A single line: `ok <message>` or `error <message>`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let status = if self.is_ok { "ok" } else { "error" };
+        let message = self.message
+            .replace('\\', "\\\\")
+            .replace('\r', "\\r")
+            .replace('\n', "\\n");
+
+        match message.is_empty() {
+            true => write!(f, "{}", status),
+            false => write!(f, "{} {}", status, message),
+        }
+    }
+}
+
+
+/// Resource: the requests received by the listener's threads, waiting to be run on the game's thread
+pub struct RemoteConsole {
+    address: RemoteAddress,
+    requests: Mutex<Receiver<RemoteRequest>>,
+}
+
+impl RemoteConsole {
+    /// Start listening, accepting connections on a background thread.
+    ///     Binding TCP port 0 picks any free port: see `address()` for the actual one.
+    pub fn listen(address: &RemoteAddress) -> io::Result<RemoteConsole> {
+        let (sender, receiver) = mpsc::channel();
+
+        let address = match address {
+            RemoteAddress::Tcp(address) => {
+                let listener = TcpListener::bind(address)?;
+                let local_address = listener.local_addr()?;
+                thread::spawn(move || listener.incoming()
+                    .filter_map(Result::ok)
+                    .for_each(|stream| match stream.try_clone() {
+                        Ok(reader) => serve(reader, stream, sender.clone()),
+                        Err(error) => warn!("Remote console: {}", error),
+                    }));
+                RemoteAddress::Tcp(local_address)
+            },
+            #[cfg(unix)]
+            RemoteAddress::Unix(path) => {
+                // A socket left over by a previous run would prevent binding, but anything else is left alone
+                match std::fs::symlink_metadata(path) {
+                    Ok(metadata) if metadata.file_type().is_socket() => std::fs::remove_file(path)?,
+                    Ok(_) => return Err(io::Error::new(io::ErrorKind::AlreadyExists,
+                        format!("'{}' exists and is not a socket", path.display()))),
+                    Err(error) if error.kind() == io::ErrorKind::NotFound => {},
+                    Err(error) => return Err(error),
+                }
+                let listener = UnixListener::bind(path)?;
+                thread::spawn(move || listener.incoming()
+                    .filter_map(Result::ok)
+                    .for_each(|stream| match stream.try_clone() {
+                        Ok(reader) => serve(reader, stream, sender.clone()),
+                        Err(error) => warn!("Remote console: {}", error),
+                    }));
+                RemoteAddress::Unix(path.clone())
+            },
+        };
+
+        Ok(RemoteConsole { address, requests: Mutex::new(receiver) })
+    }
+
+    pub fn address(&self) -> &RemoteAddress {
+        &self.address
+    }
+
+    /// Every request received since the last call
+    pub fn pending(&self) -> Vec<RemoteRequest> {
+        self.requests.lock()
+            .map(|requests| requests.try_iter().collect())
+            .unwrap_or_default()
+    }
+}
+
+impl Drop for RemoteConsole {
+    fn drop(&mut self) {
+        #[cfg(unix)]
+        if let RemoteAddress::Unix(path) = &self.address {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
+
+/// Systems
+/// Run the pending requests' commands, and send each client its response
+pub fn handle_remote_requests(world: &mut World) {
+    let requests = match world.get_resource::<RemoteConsole>() {
+        Some(remote_console) => remote_console.pending(),
+        None => return,
+    };
+
+    requests.into_iter().for_each(|request| {
+        let response = match request.line.trim() {
+            "" => RemoteResponse { is_ok: true, message: String::new() },
+            line => RemoteResponse::from_results(&run_console_command(world, line)),
+        };
+        // The client may have disconnected in the meantime
+        let _ = request.reply.send(response);
+    });
+}
+
+
+/// Helpers
+/// Serve one client on its own thread: forward each line to the game, then wait for its response
+fn serve<R, W>(reader: R, mut writer: W, requests: Sender<RemoteRequest>)
+where
+    R: Read + Send + 'static,
+    W: Write + Send + 'static,
+{
+    thread::spawn(move || {
+        for line in BufReader::new(reader).lines() {
+            let line = match line {
+                Ok(line) => line,
+                Err(_) => break,
+            };
+
+            let (reply, response) = mpsc::channel();
+            if requests.send(RemoteRequest { line, reply }).is_err() {
+                break;
+            }
+            let response = match response.recv() {
+                Ok(response) => response,
+                Err(_) => break,
+            };
+            if writeln!(writer, "{}", response).and_then(|_| writer.flush()).is_err() {
+                break;
+            }
+        }
+    });
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpStream;
+    use std::time::{Duration, Instant};
+
+    use bevy::app::Events;
+    use bevy::prelude::*;
+
+    use crate::plugins::console::LogToConsole;
+    use crate::plugins::console::commands::{ArgumentKind, ConsoleCommand, ConsoleCommands};
+    use super::{RemoteAddress, RemoteConsole, RemoteResponse, handle_remote_requests};
+
+    fn create_world() -> World {
+        let mut commands = ConsoleCommands::default();
+        commands.register(ConsoleCommand::new("echo", "Repeat the arguments")
+            .variadic_argument("text", ArgumentKind::String)
+            .run(|_, command| Ok(command.arguments.iter().map(ToString::to_string).collect::<Vec<_>>().join(" "))));
+
+        let mut world = World::default();
+        world.insert_resource(commands);
+        world.insert_resource(Events::<LogToConsole>::default());
+        world
+    }
+
+    /// Send a request, running the game's side until its response arrives
+    fn request(world: &mut World, client: &mut TcpStream, reader: &mut impl BufRead, line: &str) -> String {
+        writeln!(client, "{}", line).unwrap();
+        client.set_read_timeout(Some(Duration::from_millis(10))).unwrap();
+
+        let started = Instant::now();
+        let mut response = String::new();
+        while started.elapsed() < Duration::from_secs(5) {
+            handle_remote_requests(world);
+            if reader.read_line(&mut response).is_ok() && response.ends_with('\n') {
+                return response.trim_end().to_string();
+            }
+        }
+        panic!("No response to '{}'", line);
+    }
+
+    #[test]
+    fn only_loopback_addresses_are_allowed() {
+        assert!(RemoteAddress::parse("127.0.0.1:27015").is_ok());
+        assert!(RemoteAddress::parse("[::1]:27015").is_ok());
+        assert!(RemoteAddress::parse("0.0.0.0:27015").is_err());
+        assert!(RemoteAddress::parse("localhost").is_err());
+    }
+
+    #[test]
+    fn responses_are_single_lines() {
+        let response = RemoteResponse { is_ok: false, message: "a\nb\\c".to_string() };
+        assert_eq!(response.to_string(), "error a\\nb\\\\c");
+        assert_eq!(RemoteResponse { is_ok: true, message: String::new() }.to_string(), "ok");
+    }
+
+    #[test]
+    fn commands_are_run_over_loopback() {
+        let mut world = create_world();
+        let remote_console = RemoteConsole::listen(&RemoteAddress::parse("127.0.0.1:0").unwrap()).unwrap();
+        let address = match remote_console.address() {
+            RemoteAddress::Tcp(address) => *address,
+            #[cfg(unix)]
+            _default => unreachable!(),
+        };
+        world.insert_resource(remote_console);
+
+        let mut client = TcpStream::connect(address).unwrap();
+        let mut reader = BufReader::new(client.try_clone().unwrap());
+
+        assert_eq!(request(&mut world, &mut client, &mut reader, "echo hello world"), "ok hello world");
+        assert_eq!(request(&mut world, &mut client, &mut reader, "echo a; echo b"), "ok a\\nb");
+        assert!(request(&mut world, &mut client, &mut reader, "nope").starts_with("error Unknown command: 'nope'"));
+        assert_eq!(request(&mut world, &mut client, &mut reader, ""), "ok");
+
+        // Commands are echoed to the console
+        let log_to_console = world.get_resource::<Events<LogToConsole>>().unwrap();
+        let messages: Vec<String> = log_to_console.get_reader().iter(log_to_console).map(|it| it.0.clone()).collect();
+        assert_eq!(messages[0..2], ["> echo hello world".to_string(), "hello world".to_string()]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn only_stale_sockets_are_replaced() {
+        let path = std::env::temp_dir().join(format!("bevy_game_remote_test_{}.sock", std::process::id()));
+        let address = RemoteAddress::Unix(path.clone());
+
+        // A socket left by a previous run is replaced, and removed along with the RemoteConsole
+        drop(std::os::unix::net::UnixListener::bind(&path).unwrap());
+        assert!(RemoteConsole::listen(&address).is_ok());
+        assert!(!path.exists());
+
+        // Any other file is kept
+        std::fs::write(&path, "not a socket").unwrap();
+        assert!(RemoteConsole::listen(&address).is_err());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "not a socket");
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,279 @@
+/// Console scripting: `;`-separated command chains, aliases, and script files run with `exec`.
+///     `autoexec.cfg` runs whenever the game enters `GameState::Playing`, so that QA can set up scenarios.
+///     Scripts are looked up in the user configuration directory first, then in `assets/`.
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use bevy::prelude::*;
+use bevy::app::Events;
+
+use crate::plugins::console::LogToConsole;
+use crate::plugins::console::commands::{CommandError, CommandResult, ConsoleCommands, execute_command_line};
+use crate::plugins::console::parser::{CommandLine, split_commands};
+use crate::utils::config;
+
+
+/// Run when entering `GameState::Playing`, if it exists
+pub const AUTOEXEC_FILE: &str = "autoexec.cfg";
+
+/// Aliases and scripts nested deeper than this are assumed to be recursive
+const MAX_DEPTH: usize = 16;
+
+
+/// Aliases defined with `alias <name> "<commands>"`, by name
+#[derive(Debug, Default, Clone)]
+pub struct ConsoleAliases(BTreeMap<String, String>);
+
+/// How deeply aliases and scripts are currently nested
+#[derive(Debug, Default)]
+struct ScriptDepth(usize);
+
+
+/// Run a line of `;`-separated commands, expanding aliases. Every command runs, even if one fails.
+pub fn execute(world: &mut World, line: &str) -> Vec<CommandResult> {
+    split_commands(line).iter()
+        .map(|it| execute_one(world, it))
+        .collect()
+}
+
+/// Run a single command, or the commands of an alias (followed by any extra arguments, e.g. `sc 0 10 0`)
+fn execute_one(world: &mut World, line: &str) -> CommandResult {
+    let mut words = line.splitn(2, char::is_whitespace);
+    let name = words.next().unwrap_or_default().to_lowercase();
+    let arguments = words.next().unwrap_or_default().trim();
+
+    let is_command = world.get_resource::<ConsoleCommands>()
+        .map_or(false, |it| it.find(&name).is_some());
+    let alias = world.get_resource::<ConsoleAliases>()
+        .and_then(|it| it.0.get(&name))
+        .cloned();
+
+    match alias {
+        Some(commands) if !is_command => {
+            let line = if arguments.is_empty() { commands } else { format!("{} {}", commands, arguments) };
+            let results = nested(world, &name, |world| execute(world, &line))?;
+            join_results(results.into_iter().map(|it| (None, it)))
+        },
+        _default => execute_command_line(world, line),
+    }
+}
+
+/// Run `run` one level deeper, failing if the nesting is too deep (e.g. an alias calling itself)
+fn nested<T>(world: &mut World, name: &str, run: impl FnOnce(&mut World) -> T) -> Result<T, CommandError> {
+    let depth = world.get_resource_or_insert_with(ScriptDepth::default).0;
+    if depth >= MAX_DEPTH {
+        return Err(format!("'{}' is nested more than {} times: is it recursive?", name, MAX_DEPTH).into());
+    }
+
+    world.get_resource_mut::<ScriptDepth>().unwrap().0 += 1;
+    let result = run(world);
+    world.get_resource_mut::<ScriptDepth>().unwrap().0 -= 1;
+    Ok(result)
+}
+
+/// Combine the results of several commands: the messages of every command, or of every failure
+fn join_results(results: impl Iterator<Item = (Option<String>, CommandResult)>) -> CommandResult {
+    let mut messages: Vec<String> = Vec::new();
+    let mut errors: Vec<String> = Vec::new();
+
+    results.for_each(|(location, result)| {
+        let prefix = location.map_or_else(String::new, |it| format!("{}: ", it));
+        match result {
+            Ok(message) if message.is_empty() => {},
+            Ok(message) => messages.push(format!("{}{}", prefix, message)),
+            Err(error) => errors.push(format!("{}{}", prefix, error)),
+        }
+    });
+
+    if errors.is_empty() {
+        Ok(messages.join("\n"))
+    } else {
+        Err(messages.into_iter().chain(errors).collect::<Vec<String>>().join("\n").into())
+    }
+}
+
+
+/// A script's path: absolute, or relative to the user configuration directory, then to `assets/`
+pub fn find_script(path: &str) -> Option<PathBuf> {
+    let path = Path::new(path);
+    if path.is_absolute() {
+        return Some(path.to_path_buf()).filter(|it| it.exists());
+    }
+
+    config::get_user_config_dir()
+        .into_iter()
+        .chain(config::get_assets_dir())
+        .map(|it| it.join(path))
+        .find(|it| it.exists())
+}
+
+/// Run every line of a script. Blank lines, and comments starting with `//` or `#`, are skipped.
+pub fn execute_script(world: &mut World, path: &Path) -> CommandResult {
+    let contents = std::fs::read_to_string(path)
+        .map_err(|error| format!("Unable to read '{}': {}", path.display(), error))?;
+    let file_name = path.file_name().map_or_else(|| path.display().to_string(), |it| it.to_string_lossy().to_string());
+
+    let results = nested(world, &file_name, |world| {
+        let mut results: Vec<(Option<String>, CommandResult)> = Vec::new();
+        contents.lines()
+            .enumerate()
+            .map(|(index, line)| (index + 1, line.trim()))
+            .filter(|(_, line)| !line.is_empty() && !line.starts_with("//") && !line.starts_with('#'))
+            .for_each(|(number, line)| execute(world, line).into_iter()
+                .for_each(|result| results.push((Some(format!("{}:{}", file_name, number)), result))));
+        results
+    })?;
+    join_results(results.into_iter())
+}
+
+
+/// Commands
+/// `exec <path>`
+pub fn exec(world: &mut World, command: &CommandLine) -> CommandResult {
+    let path = command.require(0, "a script path", |it| Some(it.to_string()))?;
+    let script = find_script(&path)
+        .ok_or_else(|| format!("Script not found: '{}'", path))?;
+    execute_script(world, &script)
+}
+
+/// `alias [name] [commands...]`: list the aliases, show one, or define one
+pub fn alias(world: &mut World, command: &CommandLine) -> CommandResult {
+    let name = match command.argument(0) {
+        Some(name) => name.to_string().to_lowercase(),
+        None => {
+            let aliases = world.get_resource_or_insert_with(ConsoleAliases::default);
+            return Ok(match aliases.0.is_empty() {
+                true => "No aliases".to_string(),
+                false => aliases.0.iter()
+                    .map(|(name, commands)| format!("{} = \"{}\"", name, commands))
+                    .collect::<Vec<String>>()
+                    .join("\n"),
+            });
+        },
+    };
+
+    let commands: Vec<String> = command.arguments.iter()
+        .skip(1)
+        .map(ToString::to_string)
+        .chain(command.named_arguments.iter().map(|(name, value)| format!("{}={}", name, value)))
+        .collect();
+    if commands.is_empty() {
+        let aliases = world.get_resource_or_insert_with(ConsoleAliases::default);
+        return aliases.0.get(&name)
+            .map(|commands| format!("{} = \"{}\"", name, commands))
+            .ok_or_else(|| format!("Unknown alias: '{}'", name).into());
+    }
+
+    let is_command = world.get_resource::<ConsoleCommands>()
+        .map_or(false, |it| it.find(&name).is_some());
+    if is_command {
+        return Err(format!("'{}' is a command, and cannot be redefined", name).into());
+    }
+
+    let commands = commands.join(" ");
+    let message = format!("{} = \"{}\"", name, commands);
+    world.get_resource_or_insert_with(ConsoleAliases::default).0.insert(name, commands);
+    Ok(message)
+}
+
+/// `unalias <name>`
+pub fn unalias(world: &mut World, command: &CommandLine) -> CommandResult {
+    let name = command.require(0, "an alias name", |it| Some(it.to_string().to_lowercase()))?;
+    world.get_resource_or_insert_with(ConsoleAliases::default).0.remove(&name)
+        .map(|_| format!("Removed alias '{}'", name))
+        .ok_or_else(|| format!("Unknown alias: '{}'", name).into())
+}
+
+
+/// Systems
+/// Run `autoexec.cfg` if there is one, logging its output to the console
+pub fn run_autoexec(world: &mut World) {
+    let script = match find_script(AUTOEXEC_FILE) {
+        Some(script) => script,
+        None => return,
+    };
+
+    info!("Running '{}'", script.display());
+    let message = match execute_script(world, &script) {
+        Ok(message) => message,
+        Err(error) => error.to_string(),
+    };
+
+    if let Some(mut log_to_console) = world.get_resource_mut::<Events<LogToConsole>>() {
+        log_to_console.send(LogToConsole(format!("> exec {}", AUTOEXEC_FILE)));
+        if !message.is_empty() {
+            log_to_console.send(LogToConsole(message));
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use bevy::prelude::*;
+
+    use crate::plugins::console::commands::{ArgumentKind, ConsoleCommand, ConsoleCommands};
+    use crate::plugins::console::parser::parse_command_line;
+    use super::{alias, execute, execute_script};
+
+    /// A World with `say <text...>`, which records what it says, and the alias commands
+    fn create_world() -> (World, Arc<Mutex<Vec<String>>>) {
+        let said = Arc::new(Mutex::new(Vec::new()));
+        let said_by_command = said.clone();
+
+        let mut commands = ConsoleCommands::default();
+        commands.register(ConsoleCommand::new("say", "Say something")
+            .variadic_argument("text", ArgumentKind::String)
+            .run(move |_, command| {
+                let text: Vec<String> = command.arguments.iter().map(ToString::to_string).collect();
+                said_by_command.lock().unwrap().push(text.join(" "));
+                Ok(String::new())
+            }));
+        commands.register(ConsoleCommand::new("alias", "Define an alias")
+            .variadic_argument("commands", ArgumentKind::String)
+            .run(alias));
+
+        let mut world = World::default();
+        world.insert_resource(commands);
+        (world, said)
+    }
+
+    #[test]
+    fn chained_commands_all_run() {
+        let (mut world, said) = create_world();
+
+        let results = execute(&mut world, "say a; nope; say 'b; c'");
+        assert_eq!(results.len(), 3);
+        assert!(results[1].is_err());
+        assert_eq!(*said.lock().unwrap(), vec!["a", "b; c"]);
+    }
+
+    #[test]
+    fn aliases_expand_with_their_arguments() {
+        let (mut world, said) = create_world();
+
+        assert!(execute(&mut world, "alias greet \"say hello; say\"")[0].is_ok());
+        assert!(execute(&mut world, "greet world")[0].is_ok());
+        assert_eq!(*said.lock().unwrap(), vec!["hello", "world"]);
+
+        // Commands cannot be redefined, and recursion is stopped
+        assert!(alias(&mut world, &parse_command_line("alias say hi").unwrap()).is_err());
+        assert!(execute(&mut world, "alias loop loop")[0].is_ok());
+        assert!(execute(&mut world, "loop")[0].is_err());
+    }
+
+    #[test]
+    fn scripts_skip_comments_and_report_failing_lines() {
+        let (mut world, said) = create_world();
+        let path = std::env::temp_dir().join(format!("console_script_{}.cfg", std::process::id()));
+        std::fs::write(&path, "// Setup\n\nsay one\n# comment\nunknown\nsay two; say three\n").unwrap();
+
+        let error = execute_script(&mut world, &path).unwrap_err().to_string();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(*said.lock().unwrap(), vec!["one", "two", "three"]);
+        assert!(error.contains(":5: Unknown command: 'unknown'"), "{}", error);
+    }
+}
@@ -0,0 +1,221 @@
+/// The console's history panel: a capped buffer of lines, wrapped to the panel's width and scrolled by rows.
+///     Kept apart from the rendering, which only asks for the rows currently in view.
+use std::collections::VecDeque;
+
+use bevy::prelude::*;
+
+
+/// Oldest lines are dropped beyond this many
+pub const SCROLLBACK_CAPACITY: usize = 1000;
+
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConsoleLine {
+    pub text: String,
+    pub color: Color,
+}
+
+
+#[derive(Debug, Clone)]
+pub struct ConsoleScrollback {
+    lines: VecDeque<ConsoleLine>,
+    capacity: usize,
+    /// How many rows the view is scrolled up from the newest one
+    scroll: usize,
+}
+
+impl Default for ConsoleScrollback {
+    fn default() -> Self {
+        ConsoleScrollback::new(SCROLLBACK_CAPACITY)
+    }
+}
+
+impl ConsoleScrollback {
+    pub fn new(capacity: usize) -> ConsoleScrollback {
+        ConsoleScrollback { lines: VecDeque::new(), capacity, scroll: 0 }
+    }
+
+    /// Add a message (one line per `\n`), and jump back to the newest output
+    pub fn push(&mut self, text: &str, color: Color) {
+        text.lines().for_each(|it| {
+            if self.lines.len() >= self.capacity {
+                self.lines.pop_front();
+            }
+            self.lines.push_back(ConsoleLine { text: it.to_string(), color });
+        });
+        self.scroll = 0;
+    }
+
+    /// Scroll towards older lines, within the rows that exist at the given layout
+    pub fn scroll_up(&mut self, rows: usize, layout: ScrollbackLayout) {
+        let max_scroll = self.total_rows(layout.columns).saturating_sub(layout.rows);
+        self.scroll = (self.scroll + rows).min(max_scroll);
+    }
+
+    pub fn scroll_down(&mut self, rows: usize) {
+        self.scroll = self.scroll.saturating_sub(rows);
+    }
+
+    pub fn scroll_to_bottom(&mut self) {
+        self.scroll = 0;
+    }
+
+    /// Number of rows once every line is wrapped at `columns`
+    pub fn total_rows(&self, columns: usize) -> usize {
+        self.lines.iter().map(|it| wrap(&it.text, columns).len()).sum()
+    }
+
+    /// The rows in view, oldest first. When scrolled up, the last row gives way to how many rows are below.
+    pub fn visible_rows(&self, layout: ScrollbackLayout) -> Vec<ConsoleLine> {
+        if layout.rows == 0 {
+            return Vec::new();
+        }
+
+        // Wrap from the newest line backwards, only as far as needed
+        let mut rows: Vec<ConsoleLine> = Vec::new();
+        for line in self.lines.iter().rev() {
+            if rows.len() >= layout.rows + self.scroll {
+                break;
+            }
+            wrap(&line.text, layout.columns).into_iter().rev()
+                .for_each(|text| rows.push(ConsoleLine { text, color: line.color }));
+        }
+
+        let scroll = self.scroll.min(rows.len().saturating_sub(layout.rows));
+        let mut visible: Vec<ConsoleLine> = rows.into_iter()
+            .skip(scroll)
+            .take(layout.rows)
+            .collect();
+        visible.reverse();
+
+        if scroll > 0 {
+            visible.pop();
+            visible.push(ConsoleLine {
+                text: format!("-- {} more rows below (PageDown) --", scroll + 1),
+                color: Color::GRAY,
+            });
+        }
+        visible
+    }
+}
+
+
+/// How many rows fit in the panel, and how many characters in a row
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScrollbackLayout {
+    pub columns: usize,
+    pub rows: usize,
+}
+
+impl ScrollbackLayout {
+    /// Estimate the layout of a panel, for a font whose glyphs are about half as wide as they are high
+    pub fn fit(width: f32, height: f32, font_size: f32) -> ScrollbackLayout {
+        const GLYPH_ASPECT_RATIO: f32 = 0.5;
+        const LINE_SPACING: f32 = 1.2;
+
+        ScrollbackLayout {
+            columns: ((width / (font_size * GLYPH_ASPECT_RATIO)).floor() as usize).max(1),
+            rows: (height / (font_size * LINE_SPACING)).floor() as usize,
+        }
+    }
+}
+
+
+/// Split a line into rows of at most `columns` characters, breaking at spaces where possible
+pub fn wrap(text: &str, columns: usize) -> Vec<String> {
+    let columns = columns.max(1);
+    let mut rows: Vec<String> = Vec::new();
+    let mut row = String::new();
+    let mut row_length = 0;
+
+    text.split(' ').for_each(|word| {
+        let word_length = word.chars().count();
+        let separator = if row_length > 0 { 1 } else { 0 };
+
+        if row_length > 0 && row_length + separator + word_length > columns {
+            rows.push(std::mem::take(&mut row));
+            row_length = 0;
+        } else if separator > 0 {
+            row.push(' ');
+            row_length += 1;
+        }
+
+        // Words longer than a row are broken wherever the row ends
+        let mut characters = word.chars().peekable();
+        while characters.peek().is_some() {
+            if row_length == columns {
+                rows.push(std::mem::take(&mut row));
+                row_length = 0;
+            }
+            let chunk: String = characters.by_ref().take(columns - row_length).collect();
+            row_length += chunk.chars().count();
+            row.push_str(&chunk);
+        }
+    });
+
+    rows.push(row);
+    rows
+}
+
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+
+    use super::{ConsoleScrollback, ScrollbackLayout, wrap};
+
+    const LAYOUT: ScrollbackLayout = ScrollbackLayout { columns: 10, rows: 3 };
+
+    fn texts(scrollback: &ConsoleScrollback) -> Vec<String> {
+        scrollback.visible_rows(LAYOUT).into_iter().map(|it| it.text).collect()
+    }
+
+    #[test]
+    fn lines_are_wrapped_at_spaces_then_anywhere() {
+        assert_eq!(wrap("", 10), vec![""]);
+        assert_eq!(wrap("spawn cube 0 10 0", 10), vec!["spawn cube", "0 10 0"]);
+        assert_eq!(wrap("abcdefghijklmnopqrstuvwxyz", 10), vec!["abcdefghij", "klmnopqrst", "uvwxyz"]);
+        assert_eq!(wrap("a abcdefghijkl", 10), vec!["a", "abcdefghij", "kl"]);
+    }
+
+    #[test]
+    fn buffer_is_capped() {
+        let mut scrollback = ConsoleScrollback::new(5);
+        (0..8).for_each(|it| scrollback.push(&it.to_string(), Color::WHITE));
+        scrollback.push("8\n9", Color::WHITE);
+
+        assert_eq!(scrollback.total_rows(LAYOUT.columns), 5);
+        assert_eq!(texts(&scrollback), vec!["7", "8", "9"]);
+    }
+
+    #[test]
+    fn scrolling_is_clamped_and_new_output_jumps_to_the_bottom() {
+        let mut scrollback = ConsoleScrollback::default();
+        (0..5).for_each(|it| scrollback.push(&format!("line {}", it), Color::WHITE));
+
+        scrollback.scroll_up(1, LAYOUT);
+        assert_eq!(texts(&scrollback), vec!["line 1", "line 2", "-- 2 more rows below (PageDown) --"]);
+
+        scrollback.scroll_up(100, LAYOUT);
+        assert_eq!(texts(&scrollback)[0], "line 0");
+
+        scrollback.scroll_down(1);
+        assert_eq!(texts(&scrollback)[0], "line 1");
+
+        scrollback.push("line 5", Color::WHITE);
+        assert_eq!(texts(&scrollback), vec!["line 3", "line 4", "line 5"]);
+    }
+
+    #[test]
+    fn wrapped_rows_scroll_one_by_one() {
+        let mut scrollback = ConsoleScrollback::default();
+        scrollback.push("first", Color::WHITE);
+        scrollback.push("abcdefghijklmnopqrst", Color::RED);
+
+        assert_eq!(texts(&scrollback), vec!["first", "abcdefghij", "klmnopqrst"]);
+        assert_eq!(scrollback.visible_rows(LAYOUT)[2].color, Color::RED);
+
+        scrollback.scroll_up(1, LAYOUT);
+        assert_eq!(texts(&scrollback), vec!["first", "abcdefghij", "klmnopqrst"]);
+    }
+}
@@ -0,0 +1,273 @@
+/// Controls screen, reached from the menu: rebind the keys of every GameActionBinding.
+///     Changes are saved to the user's own input bindings file, never to `assets/inputs.yaml`.
+use bevy::prelude::*;
+
+use crate::GameState;
+use crate::plugins::actions::GameActionBinding;
+use crate::plugins::input::{InputBindings, InputContext, UserInputBindings};
+use crate::plugins::input::bindings::InputSource;
+use crate::plugins::loading::FontAssets;
+use crate::plugins::menu::{ButtonMaterials, get_menu_button, get_menu_button_text, get_menu_node, get_menu_text};
+
+
+/// Rebinding only applies to the Gameplay context, i.e. what the player uses while playing
+const CONTEXT: InputContext = InputContext::Gameplay;
+
+pub struct ControlsMenuPlugin;
+
+impl Plugin for ControlsMenuPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        const GAME_STATE: GameState = GameState::Controls;
+
+        app
+            .init_resource::<RebindCapture>()
+            .init_resource::<ControlsStatus>()
+            .add_system_set(SystemSet::on_enter(GAME_STATE)
+                .with_system(setup_controls_menu.system()))
+            .add_system_set(SystemSet::on_update(GAME_STATE)
+                .with_system(click_controls_button.system())
+                .with_system(capture_rebind_key.system())
+                .with_system(update_controls_text.system()))
+            .add_system_set(SystemSet::on_exit(GAME_STATE)
+                .with_system(despawn_controls_menu.system()));
+    }
+}
+
+
+/// Root node of the Controls screen
+pub struct ControlsRoot;
+
+#[derive(Clone, Copy)]
+pub enum ControlsButton {
+    Rebind(GameActionBinding),
+    Reset,
+    Back,
+}
+
+/// Texts that are refreshed whenever the bindings or the rebinding state change
+pub enum ControlsText {
+    Row(GameActionBinding),
+    Status,
+}
+
+/// The action waiting for a key press, if any
+#[derive(Debug, Default)]
+pub struct RebindCapture(pub Option<GameActionBinding>);
+
+/// Feedback for the player: conflicts, and where the bindings were saved
+#[derive(Debug, Default)]
+pub struct ControlsStatus(pub String);
+
+
+/// Systems
+fn setup_controls_menu(
+    mut commands: Commands,
+    font_assets: Res<FontAssets>,
+    button_materials: Res<ButtonMaterials>,
+) {
+    let font = font_assets.fira_sans.clone();
+
+    commands.spawn_bundle(get_menu_node(&button_materials))
+        .insert(ControlsRoot)
+        .with_children(|parent| {
+            parent.spawn_bundle(get_menu_text("Controls", font.clone(), 40.0));
+
+            GameActionBinding::ALL.iter().for_each(|&binding| {
+                parent.spawn_bundle(get_row_button(&button_materials))
+                    .insert(ControlsButton::Rebind(binding))
+                    .with_children(|parent| {
+                        parent.spawn_bundle(get_menu_text("", font.clone(), 20.0))
+                            .insert(ControlsText::Row(binding));
+                    });
+            });
+
+            parent.spawn_bundle(get_menu_text("", font.clone(), 20.0))
+                .insert(ControlsText::Status);
+
+            parent.spawn_bundle(NodeBundle {
+                style: Style {
+                    flex_direction: FlexDirection::Row,
+                    ..Default::default()
+                },
+                material: button_materials.transparent.clone(),
+                ..Default::default()
+            }).with_children(|parent| {
+                [(ControlsButton::Reset, "Reset"), (ControlsButton::Back, "Back")].iter()
+                    .for_each(|(button, label)| {
+                        parent.spawn_bundle(get_menu_button(&button_materials))
+                            .insert(*button)
+                            .with_children(|parent| {
+                                parent.spawn_bundle(get_menu_button_text(label, font.clone()));
+                            });
+                    });
+            });
+        });
+}
+
+fn despawn_controls_menu(
+    mut commands: Commands,
+    mut rebind_capture: ResMut<RebindCapture>,
+    mut status: ResMut<ControlsStatus>,
+    root_query: Query<Entity, With<ControlsRoot>>,
+) {
+    root_query.iter().for_each(|it| commands.entity(it).despawn_recursive());
+    rebind_capture.0 = None;
+    status.0.clear();
+}
+
+fn click_controls_button(
+    mut state: ResMut<State<GameState>>,
+    mut rebind_capture: ResMut<RebindCapture>,
+    mut user_input_bindings: ResMut<UserInputBindings>,
+    mut status: ResMut<ControlsStatus>,
+    interaction_query: Query<(&Interaction, &ControlsButton), Changed<Interaction>>,
+) {
+    interaction_query.iter()
+        .filter(|(interaction, _)| **interaction == Interaction::Clicked)
+        .for_each(|(_, button)| match *button {
+            ControlsButton::Rebind(binding) => {
+                rebind_capture.0 = Some(binding);
+                status.0 = format!("Press a key for {:?}, or Escape to cancel", binding);
+            },
+            ControlsButton::Reset => {
+                rebind_capture.0 = None;
+                user_input_bindings.0 = InputBindings::default();
+                status.0 = save(&user_input_bindings);
+            },
+            ControlsButton::Back => {
+                state.set(GameState::Menu).unwrap();
+            },
+        });
+}
+
+/// Bind the next key press to the action being rebound, warning about the actions already using that key
+fn capture_rebind_key(
+    keys: Res<Input<KeyCode>>,
+    input_bindings: Res<InputBindings>,
+    mut rebind_capture: ResMut<RebindCapture>,
+    mut user_input_bindings: ResMut<UserInputBindings>,
+    mut status: ResMut<ControlsStatus>,
+) {
+    let binding = match rebind_capture.0 {
+        Some(binding) => binding,
+        None => return,
+    };
+    let key = match keys.get_just_pressed().next() {
+        Some(&key) => key,
+        None => return,
+    };
+
+    rebind_capture.0 = None;
+    if key == KeyCode::Escape {
+        status.0 = format!("Cancelled rebinding {:?}", binding);
+        return;
+    }
+
+    let conflicts = rebind_conflicts(&input_bindings, binding, key);
+    user_input_bindings.0.rebind_keys(CONTEXT, binding, &[key]);
+    status.0 = save(&user_input_bindings);
+
+    if !conflicts.is_empty() {
+        let warning = format!("Warning: {:?} is also bound to {}", key, conflicts.join(", "));
+        warn!("{}", warning);
+        status.0 = format!("{}\n{}", warning, status.0);
+    }
+}
+
+fn update_controls_text(
+    input_bindings: Res<InputBindings>,
+    rebind_capture: Res<RebindCapture>,
+    status: Res<ControlsStatus>,
+    mut text_query: Query<(&mut Text, &ControlsText)>,
+) {
+    if !(input_bindings.is_changed() || rebind_capture.is_changed() || status.is_changed()) {
+        return;
+    }
+
+    text_query.iter_mut().for_each(|(mut text, controls_text)| {
+        text.sections[0].value = match *controls_text {
+            ControlsText::Row(binding) if rebind_capture.0 == Some(binding) =>
+                format!("{:?}: press a key...", binding),
+            ControlsText::Row(binding) => format!("{:?}: {}", binding, describe_inputs(&input_bindings, binding)),
+            ControlsText::Status => status.0.clone(),
+        };
+    });
+}
+
+
+/// Helpers
+fn save(user_input_bindings: &UserInputBindings) -> String {
+    match user_input_bindings.save() {
+        Ok(message) => {
+            info!("{}", message);
+            message
+        },
+        Err(message) => {
+            error!("{}", message);
+            message
+        },
+    }
+}
+
+/// Other actions bound to the key, with or without modifiers or a sequence, e.g. `Jump (Space)`, `Crouch (Ctrl+C)`.
+///     Chorded bindings count too: `Ctrl+C` suppresses a plain `C` while Ctrl is held.
+fn rebind_conflicts(input_bindings: &InputBindings, binding: GameActionBinding, key: KeyCode) -> Vec<String> {
+    input_bindings.iter()
+        .filter(|it| it.context == CONTEXT && it.binding != binding && it.source == InputSource::Key(key))
+        .map(|it| format!("{:?} ({})", it.binding, it))
+        .collect()
+}
+
+/// Every input currently bound to the action, e.g. `W / Gamepad LeftStickY`
+fn describe_inputs(input_bindings: &InputBindings, binding: GameActionBinding) -> String {
+    let inputs: Vec<String> = input_bindings.iter()
+        .filter(|it| it.context == CONTEXT && it.binding == binding)
+        .map(|it| it.to_string())
+        .collect();
+
+    if inputs.is_empty() {
+        "(unbound)".to_string()
+    } else {
+        inputs.join(" / ")
+    }
+}
+
+fn get_row_button(button_materials: &ButtonMaterials) -> ButtonBundle {
+    let mut button = get_menu_button(button_materials);
+    button.style.size = Size::new(Val::Px(600.0), Val::Px(30.0));
+    button.style.margin = Rect::all(Val::Px(2.0));
+    button
+}
+
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+
+    use crate::plugins::actions::GameActionBinding;
+    use crate::plugins::input::bindings::parse_input_bindings;
+    use super::rebind_conflicts;
+
+    #[test]
+    fn conflicts_include_chorded_bindings() {
+        let input_bindings = parse_input_bindings("
+- binding: Jump
+  key: C
+- binding: Crouch
+  key: C
+  modifiers: [Ctrl]
+- binding: SpawnCubeActor
+  sequence: [G, C]
+- binding: MoveForward
+  key: C
+  context: Spectator
+".as_bytes()).unwrap();
+
+        assert_eq!(rebind_conflicts(&input_bindings, GameActionBinding::ExitGame, KeyCode::C),
+            vec!["Jump (C)", "Crouch (Ctrl+C)", "SpawnCubeActor (G, C)"]);
+        // Rebinding an action doesn't conflict with itself, and other contexts are left alone
+        assert_eq!(rebind_conflicts(&input_bindings, GameActionBinding::Jump, KeyCode::C),
+            vec!["Crouch (Ctrl+C)", "SpawnCubeActor (G, C)"]);
+        assert!(rebind_conflicts(&input_bindings, GameActionBinding::Jump, KeyCode::X).is_empty());
+    }
+}
@@ -0,0 +1,655 @@
+/// Input Bindings configuration, loaded from `assets/inputs.yaml` through the Asset system.
+use std::fmt;
+
+use bevy::prelude::*;
+use bevy::asset::{AssetLoader, BoxedFuture, LoadContext, LoadedAsset};
+use bevy::reflect::TypeUuid;
+
+use crate::plugins::actions::GameActionBinding;
+use crate::plugins::input::contexts::InputContext;
+
+
+/// Mouse (and mouse wheel) axes
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MouseAxis {
+    X,
+    Y,
+}
+
+/// Anything that can drive a GameActionBinding.
+///     Digital sources (keys and buttons) produce 1.0 while pressed, analog sources their raw value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum InputSource {
+    Key(KeyCode),
+    MouseButton(MouseButton),
+    MouseAxis(MouseAxis),
+    MouseWheel(MouseAxis),
+    GamepadButton(GamepadButtonType),
+    GamepadAxis(GamepadAxisType),
+}
+
+impl InputSource {
+    /// Parse a source from its device (the field name in `inputs.yaml`) and its variant name
+    pub fn parse(device: &str, name: &str) -> Option<InputSource> {
+        match device {
+            "key" => parse_variant(name).map(InputSource::Key),
+            "mouse_button" => parse_variant(name).map(InputSource::MouseButton),
+            "mouse_axis" => parse_variant(name).map(InputSource::MouseAxis),
+            "mouse_wheel" => parse_variant(name).map(InputSource::MouseWheel),
+            "gamepad_button" => parse_variant(name).map(InputSource::GamepadButton),
+            "gamepad_axis" => parse_variant(name).map(InputSource::GamepadAxis),
+            _default => None,
+        }
+    }
+
+    /// Whether the source reports a motion per frame (e.g. the mouse), rather than a state or a rate
+    pub fn is_relative(&self) -> bool {
+        matches!(self, InputSource::MouseAxis(_) | InputSource::MouseWheel(_))
+    }
+
+    /// Name of the device, as used for the field name in `inputs.yaml`
+    pub fn device(&self) -> &'static str {
+        match self {
+            InputSource::Key(_) => "key",
+            InputSource::MouseButton(_) => "mouse_button",
+            InputSource::MouseAxis(_) => "mouse_axis",
+            InputSource::MouseWheel(_) => "mouse_wheel",
+            InputSource::GamepadButton(_) => "gamepad_button",
+            InputSource::GamepadAxis(_) => "gamepad_axis",
+        }
+    }
+}
+
+impl fmt::Display for InputSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InputSource::Key(it) => write!(f, "{:?}", it),
+            InputSource::MouseButton(it) => write!(f, "Mouse {:?}", it),
+            InputSource::MouseAxis(it) => write!(f, "Mouse {:?}", it),
+            InputSource::MouseWheel(it) => write!(f, "Wheel {:?}", it),
+            InputSource::GamepadButton(it) => write!(f, "Gamepad {:?}", it),
+            InputSource::GamepadAxis(it) => write!(f, "Gamepad {:?}", it),
+        }
+    }
+}
+
+
+/// Modifier keys, matching either their left or right variant
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Modifier {
+    Ctrl,
+    Shift,
+    Alt,
+    Super,
+}
+
+impl Modifier {
+    pub fn keys(&self) -> [KeyCode; 2] {
+        match self {
+            Modifier::Ctrl => [KeyCode::LControl, KeyCode::RControl],
+            Modifier::Shift => [KeyCode::LShift, KeyCode::RShift],
+            Modifier::Alt => [KeyCode::LAlt, KeyCode::RAlt],
+            Modifier::Super => [KeyCode::LWin, KeyCode::RWin],
+        }
+    }
+}
+
+
+/// Additional conditions for an InputSource: held modifiers, and keys that must be pressed
+///     in order right before it (e.g. `Ctrl+S`, or `G, G`).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Chord {
+    pub modifiers: Vec<Modifier>,
+    pub sequence: Vec<KeyCode>,
+}
+
+impl Chord {
+    /// When several bindings match the same InputSource, only the most specific ones are applied
+    pub fn specificity(&self) -> usize {
+        self.modifiers.len() + self.sequence.len()
+    }
+}
+
+
+/// A single InputSource bound to a GameActionBinding, with its value modifiers.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InputBinding {
+    pub source: InputSource,
+    pub chord: Chord,
+    pub context: InputContext,
+    pub binding: GameActionBinding,
+    pub scale: f32,
+    pub invert: bool,
+    pub deadzone: f32,
+    /// For the ConsoleCommand binding: the command line to run
+    pub command: Option<String>,
+}
+
+impl InputBinding {
+    /// Apply the deadzone, inversion and scale to a raw input value
+    pub fn apply(&self, raw_value: f32) -> f32 {
+        if raw_value.abs() <= self.deadzone {
+            return 0.0;
+        }
+
+        let value = if self.invert { -raw_value } else { raw_value };
+        value * self.scale
+    }
+}
+
+impl fmt::Display for InputBinding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.chord.modifiers.iter()
+            .try_for_each(|it| write!(f, "{:?}+", it))?;
+        self.chord.sequence.iter()
+            .try_for_each(|it| write!(f, "{:?}, ", it))?;
+        write!(f, "{}", self.source)
+    }
+}
+
+
+/// Wrapper struct for the game's Input Bindings.
+///     An action may be bound to many inputs, but within an InputContext each input (source and chord)
+///     drives a single action.
+#[derive(Debug, Clone, Default)]
+pub struct InputBindings(Vec<InputBinding>);
+
+impl InputBindings {
+    pub fn iter(&self) -> impl Iterator<Item = &InputBinding> {
+        self.0.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// All sources bound to the given action
+    pub fn sources_for(&self, binding: GameActionBinding) -> impl Iterator<Item = &InputSource> {
+        self.0.iter()
+            .filter(move |it| it.binding == binding)
+            .map(|it| &it.source)
+    }
+
+    /// All keyboard keys bound to the given action
+    pub fn keys_for(&self, binding: GameActionBinding) -> impl Iterator<Item = KeyCode> + '_ {
+        self.sources_for(binding)
+            .filter_map(|it| match it {
+                InputSource::Key(key) => Some(*key),
+                _default => None,
+            })
+    }
+
+    /// Replace the keyboard bindings of an action, within the given context
+    pub fn rebind_keys(&mut self, context: InputContext, binding: GameActionBinding, keys: &[KeyCode]) {
+        self.0.retain(|it| !(it.context == context
+            && it.binding == binding
+            && matches!(it.source, InputSource::Key(_))));
+
+        self.0.extend(keys.iter().map(|&key| InputBinding {
+            source: InputSource::Key(key),
+            chord: Chord::default(),
+            context,
+            binding,
+            scale: 1.0,
+            invert: false,
+            deadzone: 0.0,
+            command: None,
+        }));
+    }
+
+    /// Bind an input to a console command, replacing any command it was bound to
+    pub fn bind_command(&mut self, context: InputContext, source: InputSource, chord: Chord, command: &str) {
+        self.unbind_command(context, source, &chord);
+        self.0.push(InputBinding {
+            source,
+            chord,
+            context,
+            binding: GameActionBinding::ConsoleCommand,
+            scale: 1.0,
+            invert: false,
+            deadzone: 0.0,
+            command: Some(command.to_string()),
+        });
+    }
+
+    /// Remove the console command bound to an input, returning whether there was one
+    pub fn unbind_command(&mut self, context: InputContext, source: InputSource, chord: &Chord) -> bool {
+        let length = self.0.len();
+        self.0.retain(|it| !(it.binding == GameActionBinding::ConsoleCommand
+            && it.context == context
+            && it.source == source
+            && it.chord == *chord));
+        self.0.len() != length
+    }
+
+    /// Layer another set of bindings (e.g. the user's overrides) on top of these ones.
+    ///     For every action that the overrides bind with a given device within a given context,
+    ///     the overrides replace our bindings for that device, so that rebinding a key keeps the
+    ///     gamepad bindings of that action.
+    pub fn layered_with(&self, overrides: &InputBindings) -> InputBindings {
+        let is_overridden = |it: &InputBinding| overrides.iter().any(|other| {
+            other.context == it.context
+                && other.binding == it.binding
+                && match it.binding {
+                    // Each command binding stands on its own
+                    GameActionBinding::ConsoleCommand => other.source == it.source && other.chord == it.chord,
+                    _default => other.source.device() == it.source.device(),
+                }
+        });
+
+        InputBindings(self.iter()
+            .filter(|it| !is_overridden(it))
+            .chain(overrides.iter())
+            .cloned()
+            .collect())
+    }
+}
+
+
+/// Typed Asset for an input bindings file.
+///     A file that fails validation still produces an Asset, so that the errors can be reported
+///     by whoever is listening for `AssetEvent`s, rather than only being logged by the AssetServer.
+#[derive(Debug, TypeUuid)]
+#[uuid = "0c5e1bd4-8a4f-4f0c-9a8e-4a4f5b3b6c21"]
+pub struct InputBindingsAsset(pub Result<InputBindings, Vec<InputBindingsError>>);
+
+
+/// Everything that can be wrong with an input bindings file.
+///     `index` is the position of the offending entry within the file.
+#[derive(Debug, Clone, PartialEq)]
+pub enum InputBindingsError {
+    Malformed(String),
+    MissingInput { index: usize },
+    AmbiguousInput { index: usize },
+    UnknownInput { index: usize, device: &'static str, name: String },
+    UnknownBinding { index: usize, binding: String },
+    UnknownContext { index: usize, context: String },
+    DuplicateInput { index: usize, input: String, existing: GameActionBinding },
+    MissingCommand { index: usize },
+}
+
+impl fmt::Display for InputBindingsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InputBindingsError::Malformed(reason) =>
+                write!(f, "Input bindings are not formatted properly: {}", reason),
+            InputBindingsError::MissingInput { index } =>
+                write!(f, "Input binding #{}: no input (key, mouse_button, ...) was given", index),
+            InputBindingsError::AmbiguousInput { index } =>
+                write!(f, "Input binding #{}: more than one input was given", index),
+            InputBindingsError::UnknownInput { index, device, name } =>
+                write!(f, "Input binding #{}: unknown {} '{}'", index, device, name),
+            InputBindingsError::UnknownBinding { index, binding } =>
+                write!(f, "Input binding #{}: unknown GameActionBinding '{}'", index, binding),
+            InputBindingsError::UnknownContext { index, context } =>
+                write!(f, "Input binding #{}: unknown InputContext '{}'", index, context),
+            InputBindingsError::DuplicateInput { index, input, existing } =>
+                write!(f, "Input binding #{}: '{}' is already bound to '{:?}'", index, input, existing),
+            InputBindingsError::MissingCommand { index } =>
+                write!(f, "Input binding #{}: a ConsoleCommand binding needs a command", index),
+        }
+    }
+}
+
+impl std::error::Error for InputBindingsError {}
+
+
+/// A single entry of the input bindings file, before validation.
+///     Inputs and bindings are kept as Strings so that a typo can be reported precisely.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Default)]
+#[serde(deny_unknown_fields)]
+struct RawInputBinding {
+    binding: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    context: Option<String>,
+
+    // Exactly one of these must be given
+    #[serde(skip_serializing_if = "Option::is_none")]
+    key: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sequence: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    mouse_button: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    mouse_axis: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    mouse_wheel: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    gamepad_button: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    gamepad_axis: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    modifiers: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    scale: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    invert: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    deadzone: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    command: Option<String>,
+}
+
+impl From<&InputBinding> for RawInputBinding {
+    fn from(it: &InputBinding) -> Self {
+        let mut raw = RawInputBinding {
+            binding: variant_name(&it.binding),
+            context: Some(variant_name(&it.context))
+                .filter(|_| it.context != InputContext::default()),
+            modifiers: Some(it.chord.modifiers.iter().map(variant_name).collect())
+                .filter(|it: &Vec<String>| !it.is_empty()),
+            scale: Some(it.scale).filter(|&it| it != 1.0),
+            invert: Some(it.invert).filter(|&it| it),
+            deadzone: Some(it.deadzone).filter(|&it| it != 0.0),
+            command: it.command.to_owned(),
+            ..Default::default()
+        };
+
+        match it.source {
+            InputSource::Key(key) if !it.chord.sequence.is_empty() => {
+                raw.sequence = Some(it.chord.sequence.iter()
+                    .chain(std::iter::once(&key))
+                    .map(variant_name)
+                    .collect());
+            },
+            InputSource::Key(key) => raw.key = Some(variant_name(&key)),
+            InputSource::MouseButton(button) => raw.mouse_button = Some(variant_name(&button)),
+            InputSource::MouseAxis(axis) => raw.mouse_axis = Some(variant_name(&axis)),
+            InputSource::MouseWheel(axis) => raw.mouse_wheel = Some(variant_name(&axis)),
+            InputSource::GamepadButton(button) => raw.gamepad_button = Some(variant_name(&button)),
+            InputSource::GamepadAxis(axis) => raw.gamepad_axis = Some(variant_name(&axis)),
+        }
+
+        raw
+    }
+}
+
+impl RawInputBinding {
+    /// The InputSource, and the keys of a sequence leading up to it
+    fn source(&self, index: usize) -> Result<(InputSource, Vec<KeyCode>), InputBindingsError> {
+        // A sequence is bound to its last key
+        let sequence: Option<&[String]> = self.sequence.as_ref()
+            .map(|it| it.as_slice())
+            .filter(|it| !it.is_empty());
+        let sequence_key: Option<String> = sequence
+            .and_then(|it| it.last())
+            .map(|it| it.to_owned());
+
+        let mut inputs = [
+            ("key", &self.key),
+            ("key", &sequence_key),
+            ("mouse_button", &self.mouse_button),
+            ("mouse_axis", &self.mouse_axis),
+            ("mouse_wheel", &self.mouse_wheel),
+            ("gamepad_button", &self.gamepad_button),
+            ("gamepad_axis", &self.gamepad_axis),
+        ]
+            .iter()
+            .filter_map(|(device, name)| name.as_ref().map(|name| (*device, name.to_owned())))
+            .collect::<Vec<(&'static str, String)>>();
+
+        let source = match inputs.len() {
+            0 => Err(InputBindingsError::MissingInput { index }),
+            1 => {
+                let (device, name) = inputs.remove(0);
+                InputSource::parse(device, &name)
+                    .ok_or(InputBindingsError::UnknownInput { index, device, name })
+            },
+            _default => Err(InputBindingsError::AmbiguousInput { index }),
+        }?;
+
+        let leading_keys: Vec<KeyCode> = sequence
+            .map(|it| &it[..it.len() - 1])
+            .unwrap_or_default()
+            .iter()
+            .map(|name| parse_variant(name)
+                .ok_or(InputBindingsError::UnknownInput { index, device: "key", name: name.to_owned() }))
+            .collect::<Result<_, _>>()?;
+
+        Ok((source, leading_keys))
+    }
+
+    fn modifiers(&self, index: usize) -> Result<Vec<Modifier>, InputBindingsError> {
+        let mut modifiers: Vec<Modifier> = self.modifiers.as_ref()
+            .map(|it| it.as_slice())
+            .unwrap_or_default()
+            .iter()
+            .map(|name| parse_variant(name)
+                .ok_or(InputBindingsError::UnknownInput { index, device: "modifier", name: name.to_owned() }))
+            .collect::<Result<_, _>>()?;
+
+        modifiers.sort();
+        modifiers.dedup();
+        Ok(modifiers)
+    }
+}
+
+/// Parse and validate the contents of an input bindings file.
+pub fn parse_input_bindings(bytes: &[u8]) -> Result<InputBindings, Vec<InputBindingsError>> {
+    let raw_bindings: Vec<RawInputBinding> = serde_yaml::from_slice(bytes)
+        .map_err(|error| vec![InputBindingsError::Malformed(error.to_string())])?;
+
+    let mut input_bindings: Vec<InputBinding> = Vec::new();
+    let mut errors: Vec<InputBindingsError> = Vec::new();
+
+    raw_bindings.iter().enumerate().for_each(|(index, it)| {
+        let source = it.source(index)
+            .map_err(|error| errors.push(error))
+            .ok();
+        let modifiers = it.modifiers(index)
+            .map_err(|error| errors.push(error))
+            .ok();
+        let binding: Option<GameActionBinding> = parse_variant(&it.binding);
+        let context: Option<InputContext> = match &it.context {
+            Some(context) => parse_variant(context),
+            None => Some(InputContext::default()),
+        };
+
+        if binding.is_none() {
+            errors.push(InputBindingsError::UnknownBinding { index, binding: it.binding.to_owned() });
+        }
+        if binding == Some(GameActionBinding::ConsoleCommand) && it.command.is_none() {
+            errors.push(InputBindingsError::MissingCommand { index });
+        }
+        if context.is_none() {
+            errors.push(InputBindingsError::UnknownContext {
+                index,
+                context: it.context.to_owned().unwrap_or_default()
+            });
+        }
+
+        if let (Some((source, sequence)), Some(modifiers), Some(binding), Some(context)) =
+            (source, modifiers, binding, context) {
+            let input_binding = InputBinding {
+                source,
+                chord: Chord { modifiers, sequence },
+                context,
+                binding,
+                scale: it.scale.unwrap_or(1.0),
+                invert: it.invert.unwrap_or(false),
+                deadzone: it.deadzone.unwrap_or(0.0),
+                command: it.command.to_owned(),
+            };
+
+            let existing = input_bindings.iter()
+                .find(|it| it.source == input_binding.source
+                    && it.chord == input_binding.chord
+                    && it.context == input_binding.context);
+            if let Some(existing) = existing {
+                errors.push(InputBindingsError::DuplicateInput {
+                    index,
+                    input: input_binding.to_string(),
+                    existing: existing.binding
+                });
+            } else {
+                input_bindings.push(input_binding);
+            }
+        }
+    });
+
+    if errors.is_empty() {
+        Ok(InputBindings(input_bindings))
+    } else {
+        Err(errors)
+    }
+}
+
+/// Write input bindings in the same format as `assets/inputs.yaml`
+pub fn serialize_input_bindings(input_bindings: &InputBindings) -> Result<String, serde_yaml::Error> {
+    let raw_bindings: Vec<RawInputBinding> = input_bindings.iter()
+        .map(RawInputBinding::from)
+        .collect();
+
+    serde_yaml::to_string(&raw_bindings)
+}
+
+/// Parse a key with its modifiers, e.g. `F5`, `ctrl+f5` or `Ctrl+Shift+Key1`
+pub fn parse_key_chord(text: &str) -> Option<(KeyCode, Chord)> {
+    let mut parts: Vec<String> = text.split('+').map(capitalize).collect();
+    let key: KeyCode = parse_variant(&parts.pop()?)?;
+    let mut modifiers: Vec<Modifier> = parts.iter()
+        .map(|it| parse_variant(it))
+        .collect::<Option<_>>()?;
+
+    modifiers.sort();
+    modifiers.dedup();
+    Some((key, Chord { modifiers, sequence: Vec::new() }))
+}
+
+/// Helper: `f5` into `F5`, to be lenient with the case of variant names typed in the console
+fn capitalize(name: &str) -> String {
+    let mut chars = name.trim().chars();
+    chars.next()
+        .map(|first| first.to_uppercase().chain(chars).collect())
+        .unwrap_or_default()
+}
+
+/// Helper: parse a unit enum variant (e.g. `KeyCode::Space`) from its name
+fn parse_variant<T: serde::de::DeserializeOwned>(name: &str) -> Option<T> {
+    serde_yaml::from_value(serde_yaml::Value::String(name.trim().to_string())).ok()
+}
+
+/// Helper: the name of a unit enum variant, as read by `parse_variant`
+fn variant_name<T: serde::Serialize + fmt::Debug>(variant: &T) -> String {
+    match serde_yaml::to_value(variant) {
+        Ok(serde_yaml::Value::String(name)) => name,
+        _default => format!("{:?}", variant),
+    }
+}
+
+
+/// AssetLoader for input bindings files.
+#[derive(Default)]
+pub struct InputBindingsLoader;
+
+impl AssetLoader for InputBindingsLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), anyhow::Error>> {
+        Box::pin(async move {
+            let input_bindings = InputBindingsAsset(parse_input_bindings(bytes));
+            load_context.set_default_asset(LoadedAsset::new(input_bindings));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["yaml"]
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+
+    use crate::plugins::actions::GameActionBinding;
+    use super::{Chord, InputBindingsError, InputSource, Modifier, parse_input_bindings, parse_key_chord};
+
+    fn errors(contents: &str) -> Vec<InputBindingsError> {
+        parse_input_bindings(contents.as_bytes()).unwrap_err()
+    }
+
+    #[test]
+    fn bindings_are_parsed() {
+        let input_bindings = parse_input_bindings("
+- binding: Jump
+  key: Space
+- binding: Jump
+  gamepad_button: South
+".as_bytes()).unwrap();
+
+        assert_eq!(input_bindings.len(), 2);
+        assert_eq!(input_bindings.keys_for(GameActionBinding::Jump).collect::<Vec<_>>(), vec![KeyCode::Space]);
+        assert!(input_bindings.sources_for(GameActionBinding::Jump)
+            .any(|it| *it == InputSource::GamepadButton(GamepadButtonType::South)));
+    }
+
+    #[test]
+    fn chords_are_parsed() {
+        let input_bindings = parse_input_bindings("
+- binding: Jump
+  sequence: [G, G]
+- binding: Crouch
+  key: C
+  modifiers: [Shift, Ctrl, Ctrl]
+".as_bytes()).unwrap();
+        let chords: Vec<(InputSource, &Chord)> = input_bindings.iter().map(|it| (it.source, &it.chord)).collect();
+
+        // A sequence is bound to its last key
+        assert_eq!(chords[0], (InputSource::Key(KeyCode::G), &Chord { modifiers: vec![], sequence: vec![KeyCode::G] }));
+        assert_eq!(chords[1].1.modifiers, vec![Modifier::Ctrl, Modifier::Shift]);
+        assert_eq!(chords[1].1.specificity(), 2);
+
+        assert_eq!(parse_key_chord("shift+ctrl+f5"), parse_key_chord("Ctrl+Shift+F5"));
+        assert_eq!(parse_key_chord("f5"), Some((KeyCode::F5, Chord::default())));
+        assert_eq!(parse_key_chord("Hyper+F5"), None);
+    }
+
+    #[test]
+    fn unknown_keys_are_reported() {
+        assert_eq!(errors("
+- binding: Jump
+  key: Spacebar
+"), vec![InputBindingsError::UnknownInput { index: 0, device: "key", name: "Spacebar".to_string() }]);
+    }
+
+    #[test]
+    fn unknown_bindings_are_reported() {
+        assert_eq!(errors("
+- binding: Jump
+  key: Space
+- binding: DoABarrelRoll
+  key: B
+"), vec![InputBindingsError::UnknownBinding { index: 1, binding: "DoABarrelRoll".to_string() }]);
+    }
+
+    #[test]
+    fn duplicate_inputs_are_reported() {
+        assert_eq!(errors("
+- binding: Jump
+  key: Space
+- binding: Crouch
+  key: Space
+"), vec![InputBindingsError::DuplicateInput {
+            index: 1,
+            input: "Space".to_string(),
+            existing: GameActionBinding::Jump,
+        }]);
+
+        // The same key with a modifier, or in another context, is another input
+        assert!(parse_input_bindings("
+- binding: Jump
+  key: Space
+- binding: Crouch
+  key: Space
+  modifiers: [Ctrl]
+- binding: Crouch
+  key: Space
+  context: Spectator
+".as_bytes()).is_ok());
+    }
+}
@@ -0,0 +1,186 @@
+/// Keys bound to console commands (`bind F5 "spawn cube"`), stored as ConsoleCommand InputBindings
+///     within the user's overrides, so that they are saved along with the Controls menu's bindings.
+use bevy::prelude::*;
+
+use crate::plugins::actions::GameActionBinding;
+use crate::plugins::console::HandleConsoleCommand;
+use crate::plugins::console::commands::CommandResult;
+use crate::plugins::console::parser::CommandLine;
+use crate::plugins::input::bindings::{InputBinding, InputSource, parse_key_chord};
+use crate::plugins::input::contexts::InputContext;
+use crate::plugins::input::{ActionState, InputBindings, UserInputBindings};
+
+
+/// Commands
+/// `bindings [action]`
+pub fn list_bindings(world: &mut World, command: &CommandLine) -> CommandResult {
+    let action = command.argument(0)
+        .and_then(|it| GameActionBinding::parse(&it.to_string()));
+    let input_bindings = world.get_resource::<InputBindings>()
+        .ok_or_else(|| "No input bindings are loaded".to_string())?;
+
+    let lines: Vec<String> = input_bindings.iter()
+        .filter(|it| action.map_or(true, |action| it.binding == action))
+        .map(|it| match &it.command {
+            Some(line) => format!("{:?} ({:?}): {} runs \"{}\"", it.binding, it.context, it, line),
+            None => format!("{:?} ({:?}): {}", it.binding, it.context, it),
+        })
+        .collect();
+    if lines.is_empty() {
+        Ok("No bindings".to_string())
+    } else {
+        Ok(lines.join("\n"))
+    }
+}
+
+/// `bind <key> [command...]`: show the binding of a key, or bind it to a console command
+pub fn bind(world: &mut World, command: &CommandLine) -> CommandResult {
+    let key_name = command.require(0, "a key", |it| Some(it.to_string()))?;
+    let (key, chord) = parse_key_chord(&key_name)
+        .ok_or_else(|| format!("Unknown key '{}', e.g. F5 or Ctrl+F5", key_name))?;
+    let source = InputSource::Key(key);
+    let is_same_input = |it: &&InputBinding| it.context == InputContext::Gameplay
+        && it.source == source
+        && it.chord == chord;
+
+    let input_bindings = world.get_resource::<InputBindings>()
+        .ok_or_else(|| "No input bindings are loaded".to_string())?;
+    let line: Vec<String> = command.arguments.iter()
+        .skip(1)
+        .map(ToString::to_string)
+        .chain(command.named_arguments.iter().map(|(name, value)| format!("{}={}", name, value)))
+        .collect();
+
+    if line.is_empty() {
+        return Ok(match input_bindings.iter().find(is_same_input) {
+            Some(InputBinding { command: Some(line), .. }) => format!("{} runs \"{}\"", key_name, line),
+            Some(it) => format!("{} is bound to {:?}", key_name, it.binding),
+            None => format!("{} is not bound", key_name),
+        });
+    }
+
+    let existing_action = input_bindings.iter()
+        .filter(is_same_input)
+        .find(|it| it.binding != GameActionBinding::ConsoleCommand);
+    if let Some(existing) = existing_action {
+        return Err(format!("{} is already bound to {:?}: rebind it from the Controls menu first",
+            key_name, existing.binding).into());
+    }
+
+    let line = line.join(" ");
+    let mut user_input_bindings = world.get_resource_mut::<UserInputBindings>()
+        .ok_or_else(|| "No user input bindings are loaded".to_string())?;
+    user_input_bindings.0.bind_command(InputContext::Gameplay, source, chord, &line);
+    user_input_bindings.save()?;
+    Ok(format!("{} runs \"{}\"", key_name, line))
+}
+
+/// `unbind <key>`: remove the console command bound to a key
+pub fn unbind(world: &mut World, command: &CommandLine) -> CommandResult {
+    let key_name = command.require(0, "a key", |it| Some(it.to_string()))?;
+    let (key, chord) = parse_key_chord(&key_name)
+        .ok_or_else(|| format!("Unknown key '{}', e.g. F5 or Ctrl+F5", key_name))?;
+
+    let mut user_input_bindings = world.get_resource_mut::<UserInputBindings>()
+        .ok_or_else(|| "No user input bindings are loaded".to_string())?;
+    if !user_input_bindings.0.unbind_command(InputContext::Gameplay, InputSource::Key(key), &chord) {
+        return Err(format!("{} is not bound to a console command", key_name).into());
+    }
+
+    user_input_bindings.save()?;
+    Ok(format!("{} is no longer bound", key_name))
+}
+
+
+/// Systems
+/// Run the console commands bound to the inputs just triggered, as resolved by the ActionState
+pub fn run_bound_commands(
+    action_state: Res<ActionState>,
+    mut handle_console_command: EventWriter<HandleConsoleCommand>,
+) {
+    action_state.triggered_commands()
+        .for_each(|it| handle_console_command.send(HandleConsoleCommand(it.to_string())));
+}
+
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+    use bevy::app::Events;
+    use bevy::input::ElementState;
+    use bevy::input::keyboard::KeyboardInput;
+
+    use crate::plugins::console::HandleConsoleCommand;
+    use crate::plugins::input::InputSystem;
+    use crate::plugins::input::bindings::parse_input_bindings;
+    use crate::plugins::input::contexts::{InputContext, InputContexts};
+    use crate::plugins::input::gamepad::ConnectedGamepads;
+    use crate::plugins::input::recording::FrameTime;
+    use crate::plugins::input::state::{ActionState, KeyHistory, update_action_state};
+    use super::run_bound_commands;
+
+    const BINDINGS: &str = "
+- binding: ConsoleCommand
+  key: F5
+  command: spawn cube
+- binding: ConsoleCommand
+  key: F5
+  modifiers: [Ctrl]
+  command: spectator
+";
+
+    fn create_app() -> App {
+        let mut input_contexts = InputContexts::default();
+        input_contexts.push(InputContext::Gameplay);
+
+        let mut app = App::build();
+        app
+            .add_plugins(MinimalPlugins)
+            .add_plugin(bevy::input::InputPlugin)
+            .add_event::<HandleConsoleCommand>()
+            .insert_resource(parse_input_bindings(BINDINGS.as_bytes()).unwrap())
+            .insert_resource(input_contexts)
+            .init_resource::<ActionState>()
+            .init_resource::<ConnectedGamepads>()
+            .init_resource::<KeyHistory>()
+            .init_resource::<FrameTime>()
+            .add_system(update_action_state.system()
+                .label(InputSystem::UpdateActionState))
+            .add_system(run_bound_commands.system()
+                .after(InputSystem::UpdateActionState));
+        app.app
+    }
+
+    fn send_key(app: &mut App, key: KeyCode, state: ElementState) {
+        app.world.get_resource_mut::<Events<KeyboardInput>>().unwrap()
+            .send(KeyboardInput { scan_code: 0, key_code: Some(key), state });
+    }
+
+    /// Run a frame, returning the commands it ran
+    fn update(app: &mut App) -> Vec<String> {
+        app.update();
+        app.world.get_resource_mut::<Events<HandleConsoleCommand>>().unwrap()
+            .drain()
+            .map(|it| it.0)
+            .collect()
+    }
+
+    #[test]
+    fn the_most_specific_chord_runs_its_command() {
+        let mut app = create_app();
+
+        send_key(&mut app, KeyCode::LControl, ElementState::Pressed);
+        send_key(&mut app, KeyCode::F5, ElementState::Pressed);
+        assert_eq!(update(&mut app), vec!["spectator".to_string()]);
+
+        // Holding the keys does not run the command again
+        assert!(update(&mut app).is_empty());
+
+        send_key(&mut app, KeyCode::LControl, ElementState::Released);
+        send_key(&mut app, KeyCode::F5, ElementState::Released);
+        assert!(update(&mut app).is_empty());
+
+        send_key(&mut app, KeyCode::F5, ElementState::Pressed);
+        assert_eq!(update(&mut app), vec!["spawn cube".to_string()]);
+    }
+}
@@ -0,0 +1,144 @@
+/// Input Contexts (a.k.a. action maps): which InputBindings are currently active.
+use bevy::prelude::*;
+
+
+/// Each InputBinding belongs to a single context, `Gameplay` unless stated otherwise in `inputs.yaml`.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum InputContext {
+    Menu,
+    Gameplay,
+    Spectator,
+    Console,
+}
+
+impl Default for InputContext {
+    fn default() -> Self {
+        InputContext::Gameplay
+    }
+}
+
+impl InputContext {
+    /// A consuming context blocks every input from reaching the contexts below it.
+    ///     Otherwise, only the inputs that it binds are consumed, and the rest pass through.
+    pub fn is_consuming(&self) -> bool {
+        matches!(self, InputContext::Menu | InputContext::Console)
+    }
+}
+
+
+/// Stack of active Input Contexts: inputs are resolved from the top of the stack down.
+#[derive(Debug, Default)]
+pub struct InputContexts(Vec<InputContext>);
+
+impl InputContexts {
+    /// Push a context on top of the stack, moving it there if it was already active.
+    ///     A non-consuming context goes below the topmost consuming one instead, e.g. a Spectator spawned
+    ///     while the Console is open doesn't take the inputs away from it.
+    pub fn push(&mut self, context: InputContext) {
+        self.0.retain(|&it| it != context);
+        let index = match context.is_consuming() {
+            true => self.0.len(),
+            false => self.0.iter().rposition(InputContext::is_consuming).unwrap_or(self.0.len()),
+        };
+        self.0.insert(index, context);
+        debug!("Pushed {:?} input context: {:?}", context, self.0);
+    }
+
+    /// Remove a context from the stack, returning whether it was active
+    pub fn pop(&mut self, context: InputContext) -> bool {
+        let length = self.0.len();
+        self.0.retain(|&it| it != context);
+        debug!("Popped {:?} input context: {:?}", context, self.0);
+        self.0.len() != length
+    }
+
+    pub fn top(&self) -> Option<InputContext> {
+        self.0.last().copied()
+    }
+
+    /// Whether the context is on top of the stack, i.e. it gets the first pick of every input
+    pub fn is_focused(&self, context: InputContext) -> bool {
+        self.top() == Some(context)
+    }
+
+    /// Contexts that receive inputs, from the top of the stack down to the first consuming one
+    pub fn iter_active(&self) -> impl Iterator<Item = InputContext> + '_ {
+        let mut is_blocked = false;
+        self.0.iter()
+            .rev()
+            .copied()
+            .take_while(move |it| {
+                let is_active = !is_blocked;
+                is_blocked = it.is_consuming();
+                is_active
+            })
+    }
+}
+
+
+/// Keep the Input Contexts in sync with the GameState.
+pub fn on_enter_menu(mut input_contexts: ResMut<InputContexts>) {
+    input_contexts.push(InputContext::Menu);
+}
+
+pub fn on_exit_menu(mut input_contexts: ResMut<InputContexts>) {
+    input_contexts.pop(InputContext::Menu);
+}
+
+pub fn on_enter_playing(mut input_contexts: ResMut<InputContexts>) {
+    input_contexts.push(InputContext::Gameplay);
+}
+
+pub fn on_exit_playing(mut input_contexts: ResMut<InputContexts>) {
+    [InputContext::Console, InputContext::Spectator, InputContext::Gameplay].iter()
+        .for_each(|&it| { input_contexts.pop(it); });
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::{InputContext, InputContexts};
+
+    fn contexts(pushed: &[InputContext]) -> InputContexts {
+        let mut contexts = InputContexts::default();
+        pushed.iter().for_each(|&it| contexts.push(it));
+        contexts
+    }
+
+    #[test]
+    fn pushed_contexts_get_the_focus() {
+        let mut contexts = contexts(&[InputContext::Gameplay, InputContext::Spectator]);
+        assert!(contexts.is_focused(InputContext::Spectator));
+
+        // Pushing again moves it to the top
+        contexts.push(InputContext::Gameplay);
+        assert!(contexts.is_focused(InputContext::Gameplay));
+        assert_eq!(contexts.iter_active().collect::<Vec<_>>(), [InputContext::Gameplay, InputContext::Spectator]);
+
+        assert!(contexts.pop(InputContext::Gameplay));
+        assert!(!contexts.pop(InputContext::Gameplay));
+        assert!(contexts.is_focused(InputContext::Spectator));
+    }
+
+    #[test]
+    fn consuming_contexts_keep_the_focus() {
+        // `despawn spectator; spectator` from the console
+        let mut contexts = contexts(&[InputContext::Gameplay, InputContext::Spectator, InputContext::Console]);
+        contexts.pop(InputContext::Spectator);
+        contexts.push(InputContext::Spectator);
+        assert!(contexts.is_focused(InputContext::Console));
+        assert_eq!(contexts.iter_active().collect::<Vec<_>>(), [InputContext::Console]);
+
+        // Once the console is closed, the spectator is on top
+        contexts.pop(InputContext::Console);
+        assert!(contexts.is_focused(InputContext::Spectator));
+        assert_eq!(contexts.iter_active().collect::<Vec<_>>(), [InputContext::Spectator, InputContext::Gameplay]);
+    }
+
+    #[test]
+    fn consuming_contexts_block_the_ones_below() {
+        let contexts = contexts(&[InputContext::Menu, InputContext::Console]);
+        assert_eq!(contexts.iter_active().collect::<Vec<_>>(), [InputContext::Console]);
+        assert_eq!(contexts.top(), Some(InputContext::Console));
+    }
+}
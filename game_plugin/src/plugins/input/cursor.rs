@@ -0,0 +1,152 @@
+/// Grab, lock and hide the OS cursor while the player is looking around with the mouse.
+use bevy::prelude::*;
+use bevy::window::{WindowFocused, WindowId};
+
+use crate::GameState;
+use crate::plugins::input::contexts::InputContexts;
+use crate::plugins::player::Possessed;
+
+
+/// Whether the primary window has the OS focus, tracked from `WindowFocused` events
+#[derive(Debug)]
+pub struct WindowFocus(pub bool);
+
+impl Default for WindowFocus {
+    fn default() -> Self {
+        WindowFocus(true)
+    }
+}
+
+/// Whether the cursor should currently be grabbed. Applied to the primary window whenever it changes.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct CursorGrab(pub bool);
+
+/// Everything that decides whether the cursor is grabbed, gathered so that it can be tested without a window
+#[derive(Debug, Clone, Copy)]
+pub struct CursorGrabConditions {
+    pub is_playing: bool,
+    pub is_possessing: bool,
+    pub is_ui_focused: bool,
+    pub is_window_focused: bool,
+}
+
+impl CursorGrabConditions {
+    /// Grab the cursor only when the mouse drives a Possessed pawn:
+    ///     release it for the menus and the console, and whenever the window loses focus.
+    pub fn should_grab_cursor(&self) -> bool {
+        self.is_playing && self.is_possessing && !self.is_ui_focused && self.is_window_focused
+    }
+}
+
+
+/// Systems
+pub fn track_window_focus(
+    mut window_focused: EventReader<WindowFocused>,
+    mut window_focus: ResMut<WindowFocus>,
+) {
+    window_focused.iter()
+        .filter(|it| it.id == WindowId::primary())
+        .for_each(|it| window_focus.0 = it.focused);
+}
+
+pub fn update_cursor_grab(
+    state: Res<State<GameState>>,
+    input_contexts: Res<InputContexts>,
+    window_focus: Res<WindowFocus>,
+    possessed: Query<Entity, With<Possessed>>,
+    mut cursor_grab: ResMut<CursorGrab>,
+) {
+    let conditions = CursorGrabConditions {
+        is_playing: *state.current() == GameState::Playing,
+        is_possessing: possessed.iter().next().is_some(),
+        is_ui_focused: input_contexts.top().map_or(false, |it| it.is_consuming()),
+        is_window_focused: window_focus.0,
+    };
+
+    let should_grab_cursor = conditions.should_grab_cursor();
+    // Only write on change, so that the cursor is not re-applied every frame
+    if cursor_grab.0 != should_grab_cursor {
+        cursor_grab.0 = should_grab_cursor;
+    }
+}
+
+pub fn apply_cursor_grab(
+    cursor_grab: Res<CursorGrab>,
+    windows: Option<ResMut<Windows>>,
+) {
+    let mut windows = match windows {
+        Some(windows) if cursor_grab.is_changed() => windows,
+        _default => return,
+    };
+
+    if let Some(window) = windows.get_primary_mut() {
+        debug!("Cursor {}", if cursor_grab.0 { "grabbed" } else { "released" });
+        window.set_cursor_lock_mode(cursor_grab.0);
+        window.set_cursor_visibility(!cursor_grab.0);
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+    use bevy::app::Events;
+    use bevy::window::{WindowFocused, WindowId};
+
+    use crate::GameState;
+    use crate::plugins::input::contexts::{InputContext, InputContexts};
+    use crate::plugins::player::Possessed;
+    use super::{CursorGrab, CursorGrabConditions, WindowFocus, track_window_focus, update_cursor_grab};
+
+    const PLAYING: CursorGrabConditions = CursorGrabConditions {
+        is_playing: true,
+        is_possessing: true,
+        is_ui_focused: false,
+        is_window_focused: true,
+    };
+
+    #[test]
+    fn cursor_is_grabbed_only_while_possessing_in_game() {
+        assert!(PLAYING.should_grab_cursor());
+        assert!(!CursorGrabConditions { is_playing: false, ..PLAYING }.should_grab_cursor());
+        assert!(!CursorGrabConditions { is_possessing: false, ..PLAYING }.should_grab_cursor());
+        assert!(!CursorGrabConditions { is_ui_focused: true, ..PLAYING }.should_grab_cursor());
+        assert!(!CursorGrabConditions { is_window_focused: false, ..PLAYING }.should_grab_cursor());
+    }
+
+    #[test]
+    fn console_and_focus_loss_release_the_cursor() {
+        let mut input_contexts = InputContexts::default();
+        input_contexts.push(InputContext::Gameplay);
+
+        let mut app = App::build();
+        app
+            .add_event::<WindowFocused>()
+            .add_state(GameState::Playing)
+            .insert_resource(input_contexts)
+            .init_resource::<WindowFocus>()
+            .init_resource::<CursorGrab>()
+            .add_system(track_window_focus.system().label("track_window_focus"))
+            .add_system(update_cursor_grab.system().after("track_window_focus"));
+        let mut app = app.app;
+        app.world.spawn().insert(Possessed);
+
+        let is_grabbed = |app: &App| app.world.get_resource::<CursorGrab>().unwrap().0;
+
+        app.update();
+        assert!(is_grabbed(&app));
+
+        app.world.get_resource_mut::<InputContexts>().unwrap().push(InputContext::Console);
+        app.update();
+        assert!(!is_grabbed(&app));
+
+        app.world.get_resource_mut::<InputContexts>().unwrap().pop(InputContext::Console);
+        app.update();
+        assert!(is_grabbed(&app));
+
+        app.world.get_resource_mut::<Events<WindowFocused>>().unwrap()
+            .send(WindowFocused { id: WindowId::primary(), focused: false });
+        app.update();
+        assert!(!is_grabbed(&app));
+    }
+}
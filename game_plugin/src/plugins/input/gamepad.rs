@@ -0,0 +1,137 @@
+/// Gamepad hot-plug handling. Gamepad inputs themselves are read through `InputSource`.
+use std::collections::HashSet;
+
+use bevy::prelude::*;
+
+use crate::plugins::console::LogToConsole;
+
+
+/// Gamepads that are currently connected, maintained from `GamepadEvent`s.
+///     Gamepad bindings are evaluated against every connected gamepad.
+#[derive(Debug, Default)]
+pub struct ConnectedGamepads(pub HashSet<Gamepad>);
+
+/// Track gamepads as they are plugged in and out.
+///     Bevy resets the button and axis values of a disconnected gamepad, so its actions are released.
+pub fn on_gamepad_event(
+    mut gamepad_events: EventReader<GamepadEvent>,
+    mut gamepads: ResMut<ConnectedGamepads>,
+    mut log_to_console: EventWriter<LogToConsole>,
+) {
+    gamepad_events.iter().for_each(|GamepadEvent(gamepad, event_type)| {
+        let is_changed = match event_type {
+            GamepadEventType::Connected => gamepads.0.insert(*gamepad),
+            GamepadEventType::Disconnected => gamepads.0.remove(gamepad),
+            _default => false,
+        };
+        if !is_changed {
+            return;
+        }
+
+        let message = format!("Gamepad {} {}", gamepad.0, match event_type {
+            GamepadEventType::Connected => "connected",
+            _default => "disconnected",
+        });
+
+        info!("{}", message);
+        log_to_console.send(LogToConsole(message));
+    });
+}
+
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+    use bevy::app::Events;
+    use bevy::input::gamepad::GamepadEventRaw;
+
+    use crate::plugins::actions::GameActionBinding;
+    use crate::plugins::console::LogToConsole;
+    use crate::plugins::input::InputSystem;
+    use crate::plugins::input::bindings::parse_input_bindings;
+    use crate::plugins::input::contexts::{InputContext, InputContexts};
+    use crate::plugins::input::recording::FrameTime;
+    use crate::plugins::input::state::{ActionState, KeyHistory, update_action_state};
+    use super::{ConnectedGamepads, on_gamepad_event};
+
+    const BINDINGS: &str = "
+- binding: MoveForward
+  gamepad_axis: LeftStickY
+  deadzone: 0.2
+- binding: LookUp
+  gamepad_axis: RightStickY
+  invert: true
+- binding: Jump
+  gamepad_button: RightTrigger2
+";
+
+    fn create_app() -> App {
+        let mut input_contexts = InputContexts::default();
+        input_contexts.push(InputContext::Gameplay);
+
+        let mut app = App::build();
+        app
+            .add_plugins(MinimalPlugins)
+            .add_plugin(bevy::input::InputPlugin)
+            .add_event::<LogToConsole>()
+            .insert_resource(parse_input_bindings(BINDINGS.as_bytes()).unwrap())
+            .insert_resource(input_contexts)
+            .init_resource::<ActionState>()
+            .init_resource::<ConnectedGamepads>()
+            .init_resource::<KeyHistory>()
+            .init_resource::<FrameTime>()
+            .add_system(on_gamepad_event.system()
+                .label(InputSystem::TrackGamepads))
+            .add_system(update_action_state.system()
+                .after(InputSystem::TrackGamepads));
+        app.app
+    }
+
+    fn send(app: &mut App, event_type: GamepadEventType) {
+        app.world.get_resource_mut::<Events<GamepadEventRaw>>().unwrap()
+            .send(GamepadEventRaw(Gamepad(0), event_type));
+    }
+
+    fn value(app: &App, binding: GameActionBinding) -> f32 {
+        app.world.get_resource::<ActionState>().unwrap().value(binding)
+    }
+
+    #[test]
+    fn analog_values_are_forwarded() {
+        let mut app = create_app();
+        send(&mut app, GamepadEventType::Connected);
+        send(&mut app, GamepadEventType::AxisChanged(GamepadAxisType::LeftStickY, 0.5));
+        send(&mut app, GamepadEventType::AxisChanged(GamepadAxisType::RightStickY, 0.5));
+        send(&mut app, GamepadEventType::ButtonChanged(GamepadButtonType::RightTrigger2, 0.5));
+        app.update();
+
+        assert_eq!(value(&app, GameActionBinding::MoveForward), 0.5);
+        assert_eq!(value(&app, GameActionBinding::LookUp), -0.5);
+        assert_eq!(value(&app, GameActionBinding::Jump), 0.5);
+    }
+
+    #[test]
+    fn deadzone_is_applied() {
+        let mut app = create_app();
+        send(&mut app, GamepadEventType::Connected);
+        send(&mut app, GamepadEventType::AxisChanged(GamepadAxisType::LeftStickY, 0.15));
+        app.update();
+
+        assert_eq!(value(&app, GameActionBinding::MoveForward), 0.0);
+    }
+
+    #[test]
+    fn disconnecting_releases_actions() {
+        let mut app = create_app();
+        send(&mut app, GamepadEventType::Connected);
+        send(&mut app, GamepadEventType::AxisChanged(GamepadAxisType::LeftStickY, 1.0));
+        app.update();
+        assert_eq!(value(&app, GameActionBinding::MoveForward), 1.0);
+
+        send(&mut app, GamepadEventType::Disconnected);
+        app.update();
+
+        assert!(app.world.get_resource::<ConnectedGamepads>().unwrap().0.is_empty());
+        assert_eq!(value(&app, GameActionBinding::MoveForward), 0.0);
+    }
+}
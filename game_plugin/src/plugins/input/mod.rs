@@ -0,0 +1,314 @@
+/// Convert Keyboard inputs into Game Action events, which will in turn affect gameplay.
+pub mod bindings;
+pub mod command_bindings;
+pub mod contexts;
+pub mod cursor;
+pub mod gamepad;
+pub mod overrides;
+pub mod recording;
+pub mod state;
+
+use bevy::prelude::*;
+
+use crate::GameState;
+use crate::plugins::actions;
+use crate::plugins::console::LogToConsole;
+use crate::plugins::console::commands::{AddConsoleCommand, ArgumentKind, ConsoleCommand};
+use crate::plugins::picking::Aimed;
+use crate::plugins::player::Possessed;
+
+pub use self::bindings::InputBindings;
+pub use self::contexts::{InputContext, InputContexts};
+pub use self::overrides::{DefaultInputBindings, UserInputBindings};
+pub use self::recording::FrameTime;
+pub use self::state::ActionState;
+use self::bindings::{InputBindingsAsset, InputBindingsLoader};
+use self::cursor::{CursorGrab, WindowFocus};
+use self::gamepad::ConnectedGamepads;
+use self::recording::{InputRecorder, InputRecorderSettings};
+use self::state::KeyHistory;
+
+
+/// Represents the Input handler for the Playing GameState.
+///     Input -> InputSource -> InputContext -> ActionState -> Action -> (Implementations)
+/// The above flow allows us to decouple gameplay inputs from the actions themselves,
+///     so that we can re-bind keys by updating a configuration file.
+pub struct InputPlugin;
+
+
+#[derive(SystemLabel, Debug, Clone, PartialEq, Eq, Hash)]
+pub enum InputSystem {
+    RecordOrReplay,
+    TrackGamepads,
+    UpdateActionState,
+    UpdateCursorGrab,
+}
+
+
+impl Plugin for InputPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        const GAME_STATE: GameState = GameState::Playing;
+
+        app
+            .add_asset::<InputBindingsAsset>()
+            .init_asset_loader::<InputBindingsLoader>()
+            .init_resource::<InputBindings>()
+            .init_resource::<DefaultInputBindings>()
+            .insert_resource(UserInputBindings::load())
+            .init_resource::<ActionState>()
+            .init_resource::<ConnectedGamepads>()
+            .init_resource::<KeyHistory>()
+            .init_resource::<InputContexts>()
+            .init_resource::<FrameTime>()
+            .init_resource::<WindowFocus>()
+            .init_resource::<CursorGrab>()
+            .init_resource::<InputRecorder>()
+            .insert_resource(InputRecorderSettings::from_env())
+            .add_startup_system(watch_for_changes.system())
+            .add_system_to_stage(CoreStage::PreUpdate, recording::record_or_replay_inputs.system()
+                .label(InputSystem::RecordOrReplay)
+                .after(bevy::input::InputSystem))
+            .add_system_to_stage(CoreStage::Last, recording::on_app_exit.system())
+            .add_console_command(ConsoleCommand::new("bindings", "List the input bindings, or those of one action")
+                .optional_argument("action", ArgumentKind::Binding)
+                .run(command_bindings::list_bindings))
+            .add_console_command(ConsoleCommand::new("bind",
+                "Show the binding of a key, or bind it to console commands, e.g. `bind Ctrl+F5 \"spawn cube; spectator\"`")
+                .argument("key", ArgumentKind::String)
+                .variadic_argument("command", ArgumentKind::String)
+                .run(command_bindings::bind))
+            .add_console_command(ConsoleCommand::new("unbind", "Remove the console command bound to a key")
+                .argument("key", ArgumentKind::String)
+                .run(command_bindings::unbind))
+            .add_system(on_input_bindings_changed.system())
+            .add_system(overrides::apply_user_input_bindings.system()
+                .before(InputSystem::UpdateActionState))
+            .add_system(gamepad::on_gamepad_event.system()
+                .label(InputSystem::TrackGamepads))
+            .add_system(state::update_action_state.system()
+                .label(InputSystem::UpdateActionState)
+                .after(InputSystem::TrackGamepads))
+            .add_system(send_game_actions.system()
+                .after(InputSystem::UpdateActionState))
+            .add_system(cursor::track_window_focus.system()
+                .before(InputSystem::UpdateCursorGrab))
+            .add_system(cursor::update_cursor_grab.system()
+                .label(InputSystem::UpdateCursorGrab))
+            .add_system(cursor::apply_cursor_grab.system()
+                .after(InputSystem::UpdateCursorGrab))
+
+            // Input Contexts follow the GameState
+            .add_system_set(SystemSet::on_enter(GameState::Menu)
+                .with_system(contexts::on_enter_menu.system()))
+            .add_system_set(SystemSet::on_exit(GameState::Menu)
+                .with_system(contexts::on_exit_menu.system()))
+            .add_system_set(SystemSet::on_enter(GameState::Controls)
+                .with_system(contexts::on_enter_menu.system()))
+            .add_system_set(SystemSet::on_exit(GameState::Controls)
+                .with_system(contexts::on_exit_menu.system()))
+            .add_system_set(SystemSet::on_enter(GAME_STATE)
+                .with_system(on_enter.system())
+                .with_system(contexts::on_enter_playing.system())
+                .with_system(recording::on_enter_playing.system()))
+            .add_system_set(SystemSet::on_update(GAME_STATE)
+                .with_system(command_bindings::run_bound_commands.system()
+                    .after(InputSystem::UpdateActionState))
+                .with_system(handle_game_input.system()
+                    .after(InputSystem::UpdateActionState))
+                .with_system(handle_debug_input.system()
+                    .after(InputSystem::UpdateActionState)))
+            .add_system_set(SystemSet::on_exit(GAME_STATE)
+                .with_system(on_exit.system())
+                .with_system(contexts::on_exit_playing.system())
+                .with_system(recording::save_recording.system()));
+    }
+}
+
+
+fn on_enter() {
+    debug!("on_enter");
+}
+
+
+fn on_exit() {
+    debug!("on_exit");
+}
+
+
+/// Game Input Handler: continuous actions for the Possessed pawn
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn handle_game_input(
+    player: Query<Entity, With<Possessed>>,
+    action_state: Res<ActionState>,
+    frame_time: Res<FrameTime>,
+    mut look_up: EventWriter<actions::LookUp>,
+    mut look_right: EventWriter<actions::LookRight>,
+    mut move_forward: EventWriter<actions::MoveForward>,
+    mut move_strafe: EventWriter<actions::StrafeRight>,
+    mut crouch: EventWriter<actions::Crouch>,
+    mut jump: EventWriter<actions::Jump>,
+) {
+    use actions::GameActionBinding::*;
+
+    player.for_each(|_| {
+        let forward = action_state.value(MoveForward) - action_state.value(MoveBackward);
+        let strafe = action_state.value(StrafeRight) - action_state.value(StrafeLeft);
+
+        if forward != 0.0 {
+            move_forward.send(actions::MoveForward(forward));
+        }
+        if strafe != 0.0 {
+            move_strafe.send(actions::StrafeRight(strafe));
+        }
+        if action_state.pressed(LookUp) {
+            look_up.send(actions::LookUp(action_state.frame_delta(LookUp, frame_time.delta_seconds)));
+        }
+        if action_state.pressed(LookRight) {
+            look_right.send(actions::LookRight(action_state.frame_delta(LookRight, frame_time.delta_seconds)));
+        }
+        if action_state.pressed(Crouch) {
+            crouch.send(actions::Crouch(true));
+        }
+        if action_state.pressed(Jump) {
+            jump.send(actions::Jump(true));
+        }
+    });
+}
+
+/// Broadcast the phase of every active binding, regardless of which pawn is Possessed
+fn send_game_actions(
+    action_state: Res<ActionState>,
+    mut game_actions: EventWriter<actions::GameAction>,
+) {
+    action_state.game_actions()
+        .for_each(|it| game_actions.send(it));
+}
+
+/// Handle inputs that are independent of InputMode
+fn handle_debug_input(
+    action_state: Res<ActionState>,
+    aimed: Res<Aimed>,
+    mut toggle_console: EventWriter<actions::ToggleConsole>,
+    mut spawn_cube_actor: EventWriter<actions::SpawnCubeActor>,
+    mut spawn_spectator_camera: EventWriter<actions::SpawnSpectatorCamera>,
+) {
+    use actions::GameActionBinding::*;
+
+    if action_state.just_pressed(SpawnCubeActor) {
+        // Resting against whatever is under the crosshair, like `spawn cube`
+        let mut event = actions::SpawnCubeActor::default();
+        if let Some(hit) = aimed.0 {
+            event.position = hit.resting_point(shape::Cube::default().size / 2.0);
+        }
+        spawn_cube_actor.send(event);
+    }
+    if action_state.just_pressed(SpawnSpectatorCamera) {
+        spawn_spectator_camera.send(actions::SpawnSpectatorCamera);
+    }
+    if action_state.just_pressed(ToggleConsole) {
+        toggle_console.send(actions::ToggleConsole);
+    }
+}
+
+
+/// Enable hot-reloading, so that edits to `assets/inputs.yaml` are picked up while playing.
+fn watch_for_changes(asset_server: Res<AssetServer>) {
+    if let Err(error) = asset_server.watch_for_changes() {
+        warn!("Unable to watch assets for changes: {:?}", error);
+    }
+}
+
+
+/// (Re-)apply the default input bindings whenever the configuration file is loaded or modified.
+///     Invalid files are reported, and the previous valid bindings stay active.
+fn on_input_bindings_changed(
+    mut asset_events: EventReader<AssetEvent<InputBindingsAsset>>,
+    input_bindings_assets: Res<Assets<InputBindingsAsset>>,
+    mut default_input_bindings: ResMut<DefaultInputBindings>,
+    mut log_to_console: EventWriter<LogToConsole>,
+) {
+    asset_events.iter().for_each(|event| {
+        let handle = match event {
+            AssetEvent::Created { handle } | AssetEvent::Modified { handle } => handle,
+            AssetEvent::Removed { .. } => return,
+        };
+
+        match input_bindings_assets.get(handle).map(|it| &it.0) {
+            Some(Ok(new_bindings)) => {
+                info!("Applying {} input bindings", new_bindings.len());
+                default_input_bindings.0 = new_bindings.clone();
+            },
+            Some(Err(errors)) => {
+                errors.iter().for_each(|error| {
+                    error!("{}", error);
+                    log_to_console.send(LogToConsole(error.to_string()));
+                });
+                warn!("Input bindings were not applied; keeping the previous bindings");
+            },
+            None => {}
+        }
+    });
+}
+
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+    use bevy::app::Events;
+
+    use crate::plugins::console::LogToConsole;
+    use super::bindings::{InputBindingsAsset, parse_input_bindings};
+    use super::overrides::DefaultInputBindings;
+    use super::on_input_bindings_changed;
+
+    const VALID: &str = "
+- binding: Jump
+  key: Space
+";
+
+    const INVALID: &str = "
+- binding: Jump
+  key: Spacebar
+";
+
+    fn create_app() -> App {
+        let mut app = App::build();
+        app
+            .add_plugins(MinimalPlugins)
+            .add_plugin(bevy::asset::AssetPlugin)
+            .add_asset::<InputBindingsAsset>()
+            .add_event::<LogToConsole>()
+            .init_resource::<DefaultInputBindings>()
+            .add_system(on_input_bindings_changed.system());
+        app.app
+    }
+
+    /// Asset events are sent at the end of a frame, and read on the next one
+    fn update(app: &mut App) {
+        app.update();
+        app.update();
+    }
+
+    fn default_bindings_count(app: &App) -> usize {
+        app.world.get_resource::<DefaultInputBindings>().unwrap().0.len()
+    }
+
+    #[test]
+    fn invalid_reloads_keep_the_previous_bindings() {
+        let mut app = create_app();
+        let handle = app.world.get_resource_mut::<Assets<InputBindingsAsset>>().unwrap()
+            .add(InputBindingsAsset(parse_input_bindings(VALID.as_bytes())));
+        update(&mut app);
+        assert_eq!(default_bindings_count(&app), 1);
+
+        let _ = app.world.get_resource_mut::<Assets<InputBindingsAsset>>().unwrap()
+            .set(&handle, InputBindingsAsset(parse_input_bindings(INVALID.as_bytes())));
+        update(&mut app);
+        assert_eq!(default_bindings_count(&app), 1);
+
+        // The errors are reported in the console
+        let log_to_console = app.world.get_resource::<Events<LogToConsole>>().unwrap();
+        let messages: Vec<String> = log_to_console.get_reader().iter(log_to_console).map(|it| it.0.clone()).collect();
+        assert!(messages.iter().any(|it| it.contains("unknown key 'Spacebar'")), "{:?}", messages);
+    }
+}
@@ -0,0 +1,157 @@
+/// Per-user input bindings, layered over the shipped defaults of `assets/inputs.yaml`.
+use bevy::prelude::*;
+
+use crate::plugins::input::bindings::{InputBindings, parse_input_bindings, serialize_input_bindings};
+use crate::utils::config;
+
+
+/// Name of the per-user override file, within the user configuration directory
+pub const USER_INPUT_BINDINGS_FILE: &str = "inputs.yaml";
+
+
+/// Last valid bindings from `assets/inputs.yaml`
+#[derive(Debug, Default)]
+pub struct DefaultInputBindings(pub InputBindings);
+
+/// The user's own bindings, e.g. from the Controls menu. The shipped defaults are never overwritten.
+#[derive(Debug, Default)]
+pub struct UserInputBindings(pub InputBindings);
+
+impl UserInputBindings {
+    /// Read the user's override file. A missing file simply means there are no overrides.
+    pub fn load() -> UserInputBindings {
+        let path = match config::get_user_config_file(USER_INPUT_BINDINGS_FILE) {
+            Some(path) if path.exists() => path,
+            _default => return UserInputBindings::default(),
+        };
+
+        let bytes = match std::fs::read(&path) {
+            Ok(bytes) => bytes,
+            Err(error) => {
+                error!("Unable to read user input bindings '{}': {}", path.display(), error);
+                return UserInputBindings::default();
+            }
+        };
+
+        match parse_input_bindings(&bytes) {
+            Ok(input_bindings) => {
+                info!("Loaded {} user input bindings from '{}'", input_bindings.len(), path.display());
+                UserInputBindings(input_bindings)
+            },
+            Err(errors) => {
+                errors.iter().for_each(|error| error!("{}: {}", path.display(), error));
+                UserInputBindings::default()
+            }
+        }
+    }
+
+    /// Write the user's override file, returning a message fit for the UI
+    pub fn save(&self) -> Result<String, String> {
+        let contents = serialize_input_bindings(&self.0)
+            .map_err(|error| format!("Unable to serialize input bindings: {}", error))?;
+
+        config::write_user_config_file(USER_INPUT_BINDINGS_FILE, &contents)
+            .map(|path| format!("Saved input bindings to '{}'", path.display()))
+            .map_err(|error| format!("Unable to save input bindings: {}", error))
+    }
+}
+
+
+/// Recompute the effective InputBindings whenever the defaults or the user's overrides change.
+pub fn apply_user_input_bindings(
+    default_input_bindings: Res<DefaultInputBindings>,
+    user_input_bindings: Res<UserInputBindings>,
+    mut input_bindings: ResMut<InputBindings>,
+) {
+    if default_input_bindings.is_changed() || user_input_bindings.is_changed() {
+        *input_bindings = default_input_bindings.0.layered_with(&user_input_bindings.0);
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+
+    use crate::plugins::actions::GameActionBinding;
+    use crate::plugins::input::bindings::{
+        Chord, InputBinding, InputBindings, InputSource, parse_input_bindings, serialize_input_bindings
+    };
+    use crate::plugins::input::contexts::InputContext;
+
+    fn bindings(contents: &str) -> InputBindings {
+        parse_input_bindings(contents.as_bytes()).unwrap()
+    }
+
+    fn describe(input_bindings: &InputBindings) -> Vec<String> {
+        input_bindings.iter().map(|it| format!("{:?} {}", it.binding, it)).collect()
+    }
+
+    #[test]
+    fn overrides_replace_a_device_of_an_action() {
+        let defaults = bindings("
+- binding: Jump
+  key: Space
+- binding: Jump
+  gamepad_button: South
+- binding: Crouch
+  key: C
+- binding: ConsoleCommand
+  key: F5
+  command: spawn cube
+- binding: ConsoleCommand
+  key: F6
+  command: spectator
+");
+        let mut overrides = InputBindings::default();
+        overrides.rebind_keys(InputContext::Gameplay, GameActionBinding::Jump, &[KeyCode::J]);
+        overrides.bind_command(InputContext::Gameplay, InputSource::Key(KeyCode::F5), Chord::default(), "despawn");
+
+        // Jump keeps its gamepad button, and each command binding is replaced on its own
+        let layered = defaults.layered_with(&overrides);
+        assert_eq!(describe(&layered), vec![
+            "Jump Gamepad South",
+            "Crouch C",
+            "ConsoleCommand F6",
+            "Jump J",
+            "ConsoleCommand F5",
+        ]);
+        let commands: Vec<&str> = layered.iter()
+            .filter_map(|it| it.command.as_deref())
+            .collect();
+        assert_eq!(commands, vec!["spectator", "despawn"]);
+
+        // Overrides of another context leave these ones alone
+        let mut overrides = InputBindings::default();
+        overrides.rebind_keys(InputContext::Spectator, GameActionBinding::Jump, &[KeyCode::J]);
+        assert_eq!(defaults.layered_with(&overrides).len(), defaults.len() + 1);
+    }
+
+    #[test]
+    fn serialized_bindings_read_back_the_same() {
+        let input_bindings = bindings("
+- binding: Jump
+  key: Space
+- binding: Crouch
+  key: C
+  modifiers: [Ctrl, Shift]
+  context: Spectator
+- binding: SpawnCubeActor
+  sequence: [G, G]
+- binding: LookUp
+  mouse_axis: Y
+  scale: 0.5
+  invert: true
+- binding: MoveForward
+  gamepad_axis: LeftStickY
+  deadzone: 0.1
+- binding: ConsoleCommand
+  key: F5
+  command: spawn cube
+");
+
+        let serialized = serialize_input_bindings(&input_bindings).unwrap();
+        let read_back = parse_input_bindings(serialized.as_bytes()).unwrap();
+        assert_eq!(read_back.iter().collect::<Vec<&InputBinding>>(), input_bindings.iter().collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,266 @@
+/// Input recording and deterministic replay, e.g. to reproduce a tester's bug report.
+///     Set `RECORD_INPUTS=<file>` to record everything the player does while playing,
+///     and `REPLAY_INPUTS=<file>` to feed it back, frame by frame, with the recorded frame times.
+use std::hash::Hash;
+use std::path::PathBuf;
+
+use bevy::prelude::*;
+use bevy::app::{AppExit, Events, ManualEventReader};
+use bevy::ecs::component::Component;
+use bevy::input::mouse::{MouseMotion, MouseWheel, MouseScrollUnit};
+
+
+/// Environment variables used to start a recording or a replay when entering the Playing state
+pub const RECORD_INPUTS_VARIABLE: &str = "RECORD_INPUTS";
+pub const REPLAY_INPUTS_VARIABLE: &str = "REPLAY_INPUTS";
+
+
+/// Time elapsed in the current frame: the recorded frame time while replaying, `Time` otherwise.
+///     Gameplay systems read this instead of `Time`, so that a replay is deterministic.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct FrameTime {
+    pub delta_seconds: f32,
+    pub seconds_since_startup: f64,
+}
+
+
+/// Raw keyboard and mouse state of a single frame. Gamepads are not recorded.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct InputFrame {
+    pub delta_seconds: f32,
+
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub keys: Vec<KeyCode>,
+
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub mouse_buttons: Vec<MouseButton>,
+
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub mouse_motion: [f32; 2],
+
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub mouse_wheel: [f32; 2],
+}
+
+fn is_zero(value: &[f32; 2]) -> bool {
+    *value == [0.0, 0.0]
+}
+
+/// A stream of InputFrames, stored as YAML
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct InputRecording {
+    pub frames: Vec<InputFrame>,
+}
+
+impl InputRecording {
+    pub fn load(path: &PathBuf) -> Result<InputRecording, String> {
+        let bytes = std::fs::read(path)
+            .map_err(|error| format!("Unable to read '{}': {}", path.display(), error))?;
+        serde_yaml::from_slice(&bytes)
+            .map_err(|error| format!("Unable to parse '{}': {}", path.display(), error))
+    }
+
+    pub fn save(&self, path: &PathBuf) -> Result<(), String> {
+        let contents = serde_yaml::to_string(self)
+            .map_err(|error| format!("Unable to serialize the input recording: {}", error))?;
+        std::fs::write(path, contents)
+            .map_err(|error| format!("Unable to write '{}': {}", path.display(), error))
+    }
+}
+
+
+/// Where to record to, or replay from, when entering the Playing state
+#[derive(Debug, Default, Clone)]
+pub struct InputRecorderSettings {
+    pub record_to: Option<PathBuf>,
+    pub replay_from: Option<PathBuf>,
+}
+
+impl InputRecorderSettings {
+    pub fn from_env() -> InputRecorderSettings {
+        InputRecorderSettings {
+            record_to: std::env::var_os(RECORD_INPUTS_VARIABLE).map(PathBuf::from),
+            replay_from: std::env::var_os(REPLAY_INPUTS_VARIABLE).map(PathBuf::from),
+        }
+    }
+}
+
+
+#[derive(Debug)]
+pub enum InputRecorder {
+    Idle,
+    Recording(InputRecording),
+
+    /// Replaying a recording, with the index of the next frame and the time replayed so far
+    Replaying {
+        recording: InputRecording,
+        next_frame: usize,
+        seconds_since_startup: f64,
+    },
+}
+
+impl Default for InputRecorder {
+    fn default() -> Self {
+        InputRecorder::Idle
+    }
+}
+
+impl InputRecorder {
+    pub fn replay(recording: InputRecording) -> InputRecorder {
+        InputRecorder::Replaying { recording, next_frame: 0, seconds_since_startup: 0.0 }
+    }
+
+    /// Stop recording or replaying, returning the recording in progress if any
+    pub fn stop(&mut self) -> Option<InputRecording> {
+        match std::mem::take(self) {
+            InputRecorder::Recording(recording) => Some(recording),
+            _default => None,
+        }
+    }
+}
+
+
+/// Systems
+pub fn on_enter_playing(
+    settings: Res<InputRecorderSettings>,
+    mut recorder: ResMut<InputRecorder>,
+) {
+    if let Some(path) = &settings.replay_from {
+        match InputRecording::load(path) {
+            Ok(recording) => {
+                info!("Replaying {} input frames from '{}'", recording.frames.len(), path.display());
+                *recorder = InputRecorder::replay(recording);
+            },
+            Err(error) => error!("{}", error),
+        }
+    } else if let Some(path) = &settings.record_to {
+        info!("Recording inputs to '{}'", path.display());
+        *recorder = InputRecorder::Recording(InputRecording::default());
+    }
+}
+
+/// Save the recording in progress, when leaving the Playing state or closing the game
+pub fn save_recording(
+    settings: Res<InputRecorderSettings>,
+    mut recorder: ResMut<InputRecorder>,
+) {
+    let (path, recording) = match (&settings.record_to, recorder.stop()) {
+        (Some(path), Some(recording)) => (path, recording),
+        _default => return,
+    };
+
+    match recording.save(path) {
+        Ok(()) => info!("Saved {} input frames to '{}'", recording.frames.len(), path.display()),
+        Err(error) => error!("{}", error),
+    }
+}
+
+pub fn on_app_exit(
+    mut app_exit: EventReader<AppExit>,
+    settings: Res<InputRecorderSettings>,
+    recorder: ResMut<InputRecorder>,
+) {
+    if app_exit.iter().next().is_some() {
+        save_recording(settings, recorder);
+    }
+}
+
+/// Runs right after Bevy updates its `Input` resources: either capture them,
+///     or overwrite them with the next recorded frame. Also advances the FrameTime.
+#[allow(clippy::too_many_arguments)]
+pub fn record_or_replay_inputs(
+    time: Res<Time>,
+    mut recorder: ResMut<InputRecorder>,
+    mut frame_time: ResMut<FrameTime>,
+    mut keys: ResMut<Input<KeyCode>>,
+    mut mouse_buttons: ResMut<Input<MouseButton>>,
+    mut mouse_motion: ResMut<Events<MouseMotion>>,
+    mut mouse_wheel: ResMut<Events<MouseWheel>>,
+    mut mouse_motion_reader: Local<ManualEventReader<MouseMotion>>,
+    mut mouse_wheel_reader: Local<ManualEventReader<MouseWheel>>,
+) {
+    *frame_time = FrameTime {
+        delta_seconds: time.delta_seconds(),
+        seconds_since_startup: time.seconds_since_startup(),
+    };
+
+    let mut is_replay_finished = false;
+    match &mut *recorder {
+        InputRecorder::Idle => {},
+        InputRecorder::Recording(recording) => {
+            recording.frames.push(InputFrame {
+                delta_seconds: time.delta_seconds(),
+                keys: keys.get_pressed().copied().collect(),
+                mouse_buttons: mouse_buttons.get_pressed().copied().collect(),
+                mouse_motion: sum_events(&mut mouse_motion_reader, &mouse_motion, |it| it.delta),
+                mouse_wheel: sum_events(&mut mouse_wheel_reader, &mouse_wheel, |it| Vec2::new(it.x, it.y)),
+            });
+        },
+        InputRecorder::Replaying { recording, next_frame, seconds_since_startup } => {
+            let empty_frame = InputFrame::default();
+            let previous = next_frame.checked_sub(1)
+                .and_then(|it| recording.frames.get(it))
+                .unwrap_or(&empty_frame);
+            // Once the recording is over, release everything that is still held
+            let current = recording.frames.get(*next_frame).unwrap_or(&empty_frame);
+
+            replay_pressed(&mut keys, &previous.keys, &current.keys);
+            replay_pressed(&mut mouse_buttons, &previous.mouse_buttons, &current.mouse_buttons);
+
+            mouse_motion.clear();
+            if !is_zero(&current.mouse_motion) {
+                mouse_motion.send(MouseMotion { delta: current.mouse_motion.into() });
+            }
+            mouse_wheel.clear();
+            if !is_zero(&current.mouse_wheel) {
+                let [x, y] = current.mouse_wheel;
+                mouse_wheel.send(MouseWheel { unit: MouseScrollUnit::Line, x, y });
+            }
+
+            *seconds_since_startup += current.delta_seconds as f64;
+            *frame_time = FrameTime {
+                delta_seconds: current.delta_seconds,
+                seconds_since_startup: *seconds_since_startup,
+            };
+
+            *next_frame += 1;
+            is_replay_finished = *next_frame > recording.frames.len();
+        },
+    }
+
+    if is_replay_finished {
+        info!("Input replay finished");
+        *recorder = InputRecorder::Idle;
+    }
+}
+
+
+/// Helpers
+/// Make `input` hold exactly the `current` inputs, as if they had been pressed and released by a player.
+///     Stray inputs (e.g. from the actual keyboard) are discarded.
+fn replay_pressed<T: Copy + Eq + Hash>(input: &mut Input<T>, previous: &[T], current: &[T]) {
+    let stray: Vec<T> = input.get_pressed()
+        .chain(input.get_just_pressed())
+        .copied()
+        .filter(|it| !current.contains(it))
+        .collect();
+
+    stray.into_iter().for_each(|it| if previous.contains(&it) {
+        input.release(it);
+    } else {
+        input.reset(it);
+    });
+
+    current.iter().for_each(|&it| input.press(it));
+}
+
+/// Sum the values of the events sent since the last call, without consuming them for other systems
+fn sum_events<T: Component>(
+    reader: &mut ManualEventReader<T>,
+    events: &Events<T>,
+    get_value: impl Fn(&T) -> Vec2,
+) -> [f32; 2] {
+    let total = reader.iter(events)
+        .fold(Vec2::ZERO, |total, it| total + get_value(it));
+    [total.x, total.y]
+}
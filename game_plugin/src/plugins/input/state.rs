@@ -0,0 +1,436 @@
+/// Per-frame value of every GameActionBinding, computed from all input devices.
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use bevy::prelude::*;
+use bevy::input::mouse::{MouseMotion, MouseWheel};
+
+use crate::plugins::actions::{ActionPhase, GameAction, GameActionBinding};
+use crate::plugins::input::bindings::{Chord, InputBinding, InputBindings, InputSource, MouseAxis};
+use crate::plugins::input::contexts::InputContexts;
+use crate::plugins::input::gamepad::ConnectedGamepads;
+use crate::plugins::input::recording::FrameTime;
+
+
+/// Current (and previous frame's) value of every GameActionBinding, after applying the InputBindings.
+///     Bindings that are not active this frame have a value of 0.0.
+#[derive(Debug, Default)]
+pub struct ActionState {
+    values: HashMap<GameActionBinding, f32>,
+    previous: HashMap<GameActionBinding, f32>,
+
+    /// Part of each value that comes from relative sources, i.e. the mouse
+    relative_values: HashMap<GameActionBinding, f32>,
+
+    /// Seconds since each binding was pressed, including the ones released this frame
+    held_durations: HashMap<GameActionBinding, f32>,
+
+    /// ConsoleCommand bindings that won their input this frame, and the previous one
+    commands: Vec<InputBinding>,
+    previous_commands: Vec<InputBinding>,
+}
+
+impl ActionState {
+    pub fn value(&self, binding: GameActionBinding) -> f32 {
+        self.values.get(&binding).copied().unwrap_or(0.0)
+    }
+
+    pub fn pressed(&self, binding: GameActionBinding) -> bool {
+        self.value(binding) != 0.0
+    }
+
+    pub fn just_pressed(&self, binding: GameActionBinding) -> bool {
+        self.pressed(binding) && !self.was_pressed(binding)
+    }
+
+    /// Motion of the binding over this frame: relative sources (the mouse) as they are,
+    ///     and every other source (e.g. a stick, as a rate) integrated over the frame time.
+    pub fn frame_delta(&self, binding: GameActionBinding, delta_seconds: f32) -> f32 {
+        let relative_value = self.relative_values.get(&binding).copied().unwrap_or(0.0);
+        relative_value + (self.value(binding) - relative_value) * delta_seconds
+    }
+
+    /// Command lines of the ConsoleCommand bindings triggered this frame.
+    ///     They go through the same resolution as every other binding, so `F5` does not run on `Ctrl+F5`.
+    pub fn triggered_commands(&self) -> impl Iterator<Item = &str> + '_ {
+        self.commands.iter()
+            .filter(move |it| !self.previous_commands.contains(it))
+            .filter_map(|it| it.command.as_deref())
+    }
+
+    fn previous_value(&self, binding: GameActionBinding) -> f32 {
+        self.previous.get(&binding).copied().unwrap_or(0.0)
+    }
+
+    fn was_pressed(&self, binding: GameActionBinding) -> bool {
+        self.previous_value(binding) != 0.0
+    }
+
+    /// A GameAction for every binding that is pressed, held or released this frame.
+    ///     Released actions carry the last value they had while pressed.
+    pub fn game_actions(&self) -> impl Iterator<Item = GameAction> + '_ {
+        self.held_durations.iter().map(move |(&binding, &held_duration)| {
+            let (phase, value) = if !self.pressed(binding) {
+                (ActionPhase::Released, self.previous_value(binding))
+            } else if self.was_pressed(binding) {
+                (ActionPhase::Held, self.value(binding))
+            } else {
+                (ActionPhase::Started, self.value(binding))
+            };
+
+            GameAction { binding, phase, held_duration, value }
+        })
+    }
+
+    /// Advance the held durations, once the values for this frame are known
+    fn update_held_durations(&mut self, delta_seconds: f32) {
+        let previous_durations = std::mem::take(&mut self.held_durations);
+        let previous_duration = |binding: &GameActionBinding| previous_durations.get(binding)
+            .map_or(0.0, |it| it + delta_seconds);
+
+        self.held_durations = self.values.iter()
+            .chain(self.previous.iter())
+            .filter(|(_, &value)| value != 0.0)
+            .map(|(binding, _)| (*binding, previous_duration(binding)))
+            .collect();
+    }
+}
+
+
+/// Recently pressed keys (with the time they were pressed), used to detect key sequences.
+#[derive(Debug, Default)]
+pub struct KeyHistory(pub VecDeque<(KeyCode, f64)>);
+
+impl KeyHistory {
+    const CAPACITY: usize = 8;
+
+    /// Maximum delay, in seconds, between two consecutive keys of a sequence
+    pub const SEQUENCE_STEP_TIMEOUT: f64 = 0.5;
+
+    fn push(&mut self, key: KeyCode, time: f64) {
+        if self.0.len() == Self::CAPACITY {
+            self.0.pop_front();
+        }
+        self.0.push_back((key, time));
+    }
+
+    /// Whether the most recently pressed keys are exactly `keys`, pressed in a timely fashion
+    pub fn ends_with(&self, keys: &[KeyCode]) -> bool {
+        if self.0.len() < keys.len() {
+            return false;
+        }
+
+        let mut previous_time: Option<f64> = None;
+        self.0.iter()
+            .skip(self.0.len() - keys.len())
+            .zip(keys.iter())
+            .all(|(&(key, time), expected)| {
+                let is_in_time = previous_time
+                    .map_or(true, |previous| time - previous <= Self::SEQUENCE_STEP_TIMEOUT);
+                previous_time = Some(time);
+                key == *expected && is_in_time
+            })
+    }
+}
+
+
+/// Raw device state for the current frame, used to evaluate each InputSource
+pub struct RawInputs<'a> {
+    pub keys: &'a Input<KeyCode>,
+    pub mouse_buttons: &'a Input<MouseButton>,
+    pub gamepad_buttons: &'a Input<GamepadButton>,
+    pub gamepad_button_axes: &'a Axis<GamepadButton>,
+    pub gamepad_axes: &'a Axis<GamepadAxis>,
+    pub gamepads: &'a ConnectedGamepads,
+    pub key_history: &'a KeyHistory,
+    pub mouse_motion: Vec2,
+    pub mouse_wheel: Vec2,
+}
+
+impl<'a> RawInputs<'a> {
+    /// Raw value of an InputSource, before applying the binding's modifiers
+    pub fn value(&self, source: &InputSource) -> f32 {
+        match *source {
+            InputSource::Key(key) => as_axis(self.keys.pressed(key)),
+            InputSource::MouseButton(button) => as_axis(self.mouse_buttons.pressed(button)),
+            InputSource::MouseAxis(axis) => get_axis(self.mouse_motion, axis),
+            InputSource::MouseWheel(axis) => get_axis(self.mouse_wheel, axis),
+            InputSource::GamepadButton(button_type) => self.strongest(|&gamepad| {
+                let button = GamepadButton(gamepad, button_type);
+                self.gamepad_button_axes.get(button)
+                    .unwrap_or_else(|| as_axis(self.gamepad_buttons.pressed(button)))
+            }),
+            InputSource::GamepadAxis(axis_type) => self.strongest(|&gamepad| {
+                self.gamepad_axes.get(GamepadAxis(gamepad, axis_type)).unwrap_or(0.0)
+            }),
+        }
+    }
+
+    /// Whether the modifiers of a Chord are held, and its sequence was just completed
+    pub fn matches(&self, chord: &Chord, source: &InputSource) -> bool {
+        let are_modifiers_held = chord.modifiers.iter()
+            .all(|modifier| modifier.keys().iter().any(|&key| self.keys.pressed(key)));
+
+        let is_sequence_completed = match source {
+            _any if chord.sequence.is_empty() => true,
+            InputSource::Key(key) if self.keys.just_pressed(*key) => {
+                let mut keys = chord.sequence.clone();
+                keys.push(*key);
+                self.key_history.ends_with(&keys)
+            },
+            _default => false,
+        };
+
+        are_modifiers_held && is_sequence_completed
+    }
+
+    /// The value with the largest magnitude across all connected gamepads
+    fn strongest(&self, get_value: impl Fn(&Gamepad) -> f32) -> f32 {
+        self.gamepads.0.iter()
+            .map(get_value)
+            .fold(0.0, |strongest, it| if it.abs() > strongest.abs() { it } else { strongest })
+    }
+}
+
+fn as_axis(is_pressed: bool) -> f32 {
+    if is_pressed { 1.0 } else { 0.0 }
+}
+
+fn get_axis(value: Vec2, axis: MouseAxis) -> f32 {
+    match axis {
+        MouseAxis::X => value.x,
+        MouseAxis::Y => value.y,
+    }
+}
+
+
+/// Evaluate the InputBindings of the active InputContexts against the current device state.
+///     Contexts are resolved from the top of the stack down: an InputSource bound by a higher context
+///     is consumed, and never reaches the contexts below it.
+///     Within a context, when several bindings match the same InputSource (e.g. `C` and `Shift+C`),
+///     only the most specific ones are applied, so the plain-key action is suppressed for that frame.
+#[allow(clippy::too_many_arguments)]
+pub fn update_action_state(
+    input_bindings: Res<InputBindings>,
+    input_contexts: Res<InputContexts>,
+    frame_time: Res<FrameTime>,
+    keys: Res<Input<KeyCode>>,
+    mouse_buttons: Res<Input<MouseButton>>,
+    gamepad_buttons: Res<Input<GamepadButton>>,
+    gamepad_button_axes: Res<Axis<GamepadButton>>,
+    gamepad_axes: Res<Axis<GamepadAxis>>,
+    gamepads: Res<ConnectedGamepads>,
+    mut key_history: ResMut<KeyHistory>,
+    mut mouse_motion: EventReader<MouseMotion>,
+    mut mouse_wheel: EventReader<MouseWheel>,
+    mut action_state: ResMut<ActionState>,
+) {
+    keys.get_just_pressed().for_each(|&key| {
+        key_history.push(key, frame_time.seconds_since_startup);
+    });
+
+    let raw_inputs = RawInputs {
+        keys: &keys,
+        mouse_buttons: &mouse_buttons,
+        gamepad_buttons: &gamepad_buttons,
+        gamepad_button_axes: &gamepad_button_axes,
+        gamepad_axes: &gamepad_axes,
+        gamepads: &gamepads,
+        key_history: &key_history,
+        mouse_motion: mouse_motion.iter()
+            .fold(Vec2::ZERO, |total, it| total + it.delta),
+        mouse_wheel: mouse_wheel.iter()
+            .fold(Vec2::ZERO, |total, it| total + Vec2::new(it.x, it.y)),
+    };
+
+    let action_state: &mut ActionState = &mut action_state;
+    action_state.previous = std::mem::take(&mut action_state.values);
+    action_state.previous_commands = std::mem::take(&mut action_state.commands);
+    action_state.relative_values.clear();
+
+    let mut consumed: HashSet<InputSource> = HashSet::new();
+    input_contexts.iter_active().for_each(|context| {
+        let context_bindings: Vec<&InputBinding> = input_bindings.iter()
+            .filter(|it| it.context == context && !consumed.contains(&it.source))
+            .collect();
+
+        // Bindings whose input is active this frame, with their value
+        let active_bindings: Vec<(&InputBinding, f32)> = context_bindings.iter()
+            .filter(|it| raw_inputs.matches(&it.chord, &it.source))
+            .map(|&it| (it, it.apply(raw_inputs.value(&it.source))))
+            .filter(|(_, value)| *value != 0.0)
+            .collect();
+
+        let mut specificity: HashMap<InputSource, usize> = HashMap::new();
+        active_bindings.iter().for_each(|(it, _)| {
+            let most_specific = specificity.entry(it.source).or_insert(0);
+            *most_specific = it.chord.specificity().max(*most_specific);
+        });
+
+        active_bindings.iter()
+            .filter(|(it, _)| it.chord.specificity() == specificity[&it.source])
+            .for_each(|(it, value)| {
+                *action_state.values.entry(it.binding).or_insert(0.0) += value;
+                if it.source.is_relative() {
+                    *action_state.relative_values.entry(it.binding).or_insert(0.0) += value;
+                }
+                if it.binding == GameActionBinding::ConsoleCommand {
+                    action_state.commands.push((*it).clone());
+                }
+            });
+
+        consumed.extend(context_bindings.iter().map(|it| it.source));
+    });
+
+    action_state.update_held_durations(frame_time.delta_seconds);
+}
+
+
+#[cfg(test)]
+mod tests {
+    use bevy::input::mouse::{MouseMotion, MouseWheel};
+    use bevy::prelude::*;
+
+    use crate::plugins::actions::{ActionPhase, GameAction, GameActionBinding};
+    use crate::plugins::input::bindings::parse_input_bindings;
+    use crate::plugins::input::contexts::{InputContext, InputContexts};
+    use crate::plugins::input::gamepad::ConnectedGamepads;
+    use crate::plugins::input::recording::FrameTime;
+    use super::{ActionState, KeyHistory, update_action_state};
+
+    const FRAME_SECONDS: f32 = 0.25;
+
+    fn create_app(input_bindings: &str) -> App {
+        let mut input_contexts = InputContexts::default();
+        input_contexts.push(InputContext::Gameplay);
+
+        let mut app = App::build();
+        app
+            .add_event::<MouseMotion>()
+            .add_event::<MouseWheel>()
+            .insert_resource(parse_input_bindings(input_bindings.as_bytes()).unwrap())
+            .insert_resource(input_contexts)
+            .init_resource::<FrameTime>()
+            .init_resource::<Input<KeyCode>>()
+            .init_resource::<Input<MouseButton>>()
+            .init_resource::<Input<GamepadButton>>()
+            .init_resource::<Axis<GamepadButton>>()
+            .init_resource::<Axis<GamepadAxis>>()
+            .init_resource::<ConnectedGamepads>()
+            .init_resource::<KeyHistory>()
+            .init_resource::<ActionState>()
+            .add_system(update_action_state.system());
+        app.app
+    }
+
+    /// Run a frame at `time`, with exactly `keys` held down
+    fn update(app: &mut App, time: f64, keys: &[KeyCode]) {
+        app.world.insert_resource(FrameTime { delta_seconds: FRAME_SECONDS, seconds_since_startup: time });
+
+        let mut input = app.world.get_resource_mut::<Input<KeyCode>>().unwrap();
+        input.update();
+        let released: Vec<KeyCode> = input.get_pressed().copied().filter(|it| !keys.contains(it)).collect();
+        released.into_iter().for_each(|it| input.release(it));
+        keys.iter().for_each(|&it| input.press(it));
+
+        app.update();
+    }
+
+    fn pressed(app: &App, binding: GameActionBinding) -> bool {
+        app.world.get_resource::<ActionState>().unwrap().pressed(binding)
+    }
+
+    fn game_actions(app: &App) -> Vec<GameAction> {
+        app.world.get_resource::<ActionState>().unwrap().game_actions().collect()
+    }
+
+    #[test]
+    fn actions_are_started_held_and_released() {
+        let mut app = create_app("
+- binding: Jump
+  key: Space
+");
+        let jump = |phase, held_duration, value| GameAction { binding: GameActionBinding::Jump, phase, held_duration, value };
+
+        update(&mut app, 0.0, &[KeyCode::Space]);
+        assert_eq!(game_actions(&app), vec![jump(ActionPhase::Started, 0.0, 1.0)]);
+        let action_state = app.world.get_resource::<ActionState>().unwrap();
+        assert!(action_state.just_pressed(GameActionBinding::Jump));
+
+        update(&mut app, 0.25, &[KeyCode::Space]);
+        update(&mut app, 0.5, &[KeyCode::Space]);
+        assert_eq!(game_actions(&app), vec![jump(ActionPhase::Held, 2.0 * FRAME_SECONDS, 1.0)]);
+        let action_state = app.world.get_resource::<ActionState>().unwrap();
+        assert!(action_state.pressed(GameActionBinding::Jump) && !action_state.just_pressed(GameActionBinding::Jump));
+
+        // Released actions carry their last value, and how long they were held
+        update(&mut app, 0.75, &[]);
+        assert_eq!(game_actions(&app), vec![jump(ActionPhase::Released, 3.0 * FRAME_SECONDS, 1.0)]);
+
+        update(&mut app, 1.0, &[]);
+        assert!(game_actions(&app).is_empty());
+    }
+
+    #[test]
+    fn chords_suppress_plain_keys() {
+        let mut app = create_app("
+- binding: Jump
+  key: C
+- binding: Crouch
+  key: C
+  modifiers: [Ctrl]
+");
+
+        update(&mut app, 0.0, &[KeyCode::C]);
+        assert!(pressed(&app, GameActionBinding::Jump));
+        assert!(!pressed(&app, GameActionBinding::Crouch));
+
+        update(&mut app, 0.25, &[]);
+        update(&mut app, 0.5, &[KeyCode::LControl, KeyCode::C]);
+        assert!(!pressed(&app, GameActionBinding::Jump));
+        assert!(pressed(&app, GameActionBinding::Crouch));
+
+        // Either side's modifier key will do
+        update(&mut app, 0.75, &[]);
+        update(&mut app, 1.0, &[KeyCode::RControl, KeyCode::C]);
+        assert!(pressed(&app, GameActionBinding::Crouch));
+    }
+
+    #[test]
+    fn sequences_complete_within_the_step_timeout() {
+        let mut app = create_app("
+- binding: Jump
+  sequence: [G, G]
+");
+
+        update(&mut app, 0.0, &[KeyCode::G]);
+        assert!(!pressed(&app, GameActionBinding::Jump));
+        update(&mut app, 0.1, &[]);
+        update(&mut app, 0.2, &[KeyCode::G]);
+        assert!(pressed(&app, GameActionBinding::Jump));
+
+        // Holding the last key doesn't repeat the sequence
+        update(&mut app, 0.3, &[KeyCode::G]);
+        assert!(!pressed(&app, GameActionBinding::Jump));
+
+        // Too slow: more than SEQUENCE_STEP_TIMEOUT between the two presses
+        update(&mut app, 0.4, &[]);
+        update(&mut app, 0.4 + KeyHistory::SEQUENCE_STEP_TIMEOUT + 0.1, &[KeyCode::G]);
+        assert!(!pressed(&app, GameActionBinding::Jump));
+    }
+
+    #[test]
+    fn key_history_keeps_the_latest_keys() {
+        let keys = [
+            KeyCode::Key1, KeyCode::Key2, KeyCode::Key3, KeyCode::Key4, KeyCode::Key5,
+            KeyCode::Key6, KeyCode::Key7, KeyCode::Key8, KeyCode::Key9, KeyCode::Key0,
+        ];
+        let mut key_history = KeyHistory::default();
+        keys.iter().enumerate().for_each(|(index, &it)| key_history.push(it, index as f64 * 0.1));
+
+        assert_eq!(key_history.0.len(), KeyHistory::CAPACITY);
+        assert_eq!(key_history.0.front(), Some(&(KeyCode::Key3, 0.2)));
+        assert!(key_history.ends_with(&keys[2..]));
+        assert!(!key_history.ends_with(&keys[1..]));
+        assert!(key_history.ends_with(&[]));
+    }
+}
@@ -3,6 +3,8 @@ use bevy::prelude::*;
 use bevy_asset_loader::{AssetCollection, AssetLoader};
 use bevy_kira_audio::AudioSource;
 
+use crate::plugins::input::bindings::InputBindingsAsset;
+
 pub struct LoadingPlugin;
 
 /// This plugin loads all assets using [AssetLoader] from a third party bevy plugin
@@ -14,6 +16,7 @@ impl Plugin for LoadingPlugin {
             .with_collection::<FontAssets>()
             // .with_collection::<AudioAssets>()
             .with_collection::<TextureAssets>()
+            .with_collection::<InputAssets>()
             .build(app);
     }
 }
@@ -38,3 +41,12 @@ pub struct TextureAssets {
     #[asset(path = "textures/bevy.png")]
     pub texture_bevy: Handle<Texture>,
 }
+
+#[derive(AssetCollection)]
+pub struct InputAssets {
+    /// Never read directly, but the strong handle keeps the bindings asset
+    ///     loaded so hot-reloads keep reaching `on_input_bindings_changed`
+    #[allow(dead_code)]
+    #[asset(path = "inputs.yaml")]
+    pub input_bindings: Handle<InputBindingsAsset>,
+}
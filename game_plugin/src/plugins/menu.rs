@@ -2,71 +2,95 @@ use crate::plugins::loading::FontAssets;
 use crate::GameState;
 use bevy::prelude::*;
 
-pub struct PlayButton;
+/// Root node of the main menu, despawned (with all its children) when the menu is exited
+pub struct MenuRoot;
+
+#[derive(Clone, Copy)]
+pub enum MenuButton {
+    Play,
+    Controls,
+}
 
 pub struct ButtonMaterials {
     pub normal: Handle<ColorMaterial>,
     pub hovered: Handle<ColorMaterial>,
+
+    /// For the layout nodes around the buttons
+    pub transparent: Handle<ColorMaterial>,
 }
 
 pub struct MenuPlugin;
 
-/// This plugin is responsible for the game menu (containing only one button...)
+/// This plugin is responsible for the game menu (Play, and the Controls screen)
 /// The menu is only drawn during the State `GameState::Menu` and is removed when that state is exited
 impl Plugin for MenuPlugin {
     fn build(&self, app: &mut AppBuilder) {
         app
             .init_resource::<ButtonMaterials>()
+            .add_startup_system(spawn_ui_camera.system())
+            .add_system(highlight_hovered_buttons.system())
             .add_system_set(SystemSet::on_enter(GameState::Menu)
                 .with_system(setup_menu.system()))
             .add_system_set(SystemSet::on_update(GameState::Menu)
-                .with_system(click_play_button.system()));
+                .with_system(click_menu_button.system()))
+            .add_system_set(SystemSet::on_exit(GameState::Menu)
+                .with_system(despawn_menu.system()));
     }
 }
 
+fn spawn_ui_camera(mut commands: Commands) {
+    commands.spawn_bundle(UiCameraBundle::default());
+}
+
 fn setup_menu(
     mut commands: Commands,
     font_assets: Res<FontAssets>,
     button_materials: Res<ButtonMaterials>,
 ) {
-    commands.spawn_bundle(UiCameraBundle::default());
-    commands.spawn_bundle(get_play_button(button_materials))
-        .insert(PlayButton)
+    commands.spawn_bundle(get_menu_node(&button_materials))
+        .insert(MenuRoot)
         .with_children(|parent| {
-            parent.spawn_bundle(get_play_button_text(font_assets.fira_sans.clone()));
+            [(MenuButton::Play, "Play"), (MenuButton::Controls, "Controls")].iter()
+                .for_each(|(button, label)| {
+                    parent.spawn_bundle(get_menu_button(&button_materials))
+                        .insert(*button)
+                        .with_children(|parent| {
+                            parent.spawn_bundle(get_menu_button_text(label, font_assets.fira_sans.clone()));
+                        });
+                });
         });
 }
 
-type ButtonInteraction<'a> = (
-    Entity,
-    &'a Interaction,
-    &'a mut Handle<ColorMaterial>,
-    &'a Children,
-);
+fn despawn_menu(mut commands: Commands, menu_query: Query<Entity, With<MenuRoot>>) {
+    menu_query.iter().for_each(|it| commands.entity(it).despawn_recursive());
+}
 
-fn click_play_button(
-    mut commands: Commands,
+/// Highlight every button under the cursor, in all menus
+fn highlight_hovered_buttons(
     button_materials: Res<ButtonMaterials>,
+    mut interaction_query: Query<(&Interaction, &mut Handle<ColorMaterial>), (Changed<Interaction>, With<Button>)>,
+) {
+    interaction_query.iter_mut().for_each(|(interaction, mut material)| {
+        *material = match *interaction {
+            Interaction::None => button_materials.normal.clone(),
+            _default => button_materials.hovered.clone(),
+        };
+    });
+}
+
+fn click_menu_button(
     mut state: ResMut<State<GameState>>,
-    mut interaction_query: Query<ButtonInteraction, (Changed<Interaction>, With<Button>)>,
-    text_query: Query<Entity, With<Text>>,
+    interaction_query: Query<(&Interaction, &MenuButton), Changed<Interaction>>,
 ) {
-    for (button, interaction, mut material, children) in interaction_query.iter_mut() {
-        let text = text_query.get(children[0]).unwrap();
-        match *interaction {
-            Interaction::Clicked => {
-                commands.entity(button).despawn();
-                commands.entity(text).despawn();
-                state.set(GameState::Playing).unwrap();
-            }
-            Interaction::Hovered => {
-                *material = button_materials.hovered.clone();
-            }
-            Interaction::None => {
-                *material = button_materials.normal.clone();
-            }
-        }
-    }
+    interaction_query.iter()
+        .filter(|(interaction, _)| **interaction == Interaction::Clicked)
+        .for_each(|(_, button)| {
+            let next_state = match button {
+                MenuButton::Play => GameState::Playing,
+                MenuButton::Controls => GameState::Controls,
+            };
+            state.set(next_state).unwrap();
+        });
 }
 
 impl FromWorld for ButtonMaterials {
@@ -75,15 +99,30 @@ impl FromWorld for ButtonMaterials {
         ButtonMaterials {
             normal: materials.add(Color::rgb(0.15, 0.15, 0.15).into()),
             hovered: materials.add(Color::rgb(0.25, 0.25, 0.25).into()),
+            transparent: materials.add(Color::NONE.into()),
         }
     }
 }
 
-fn get_play_button(button_materials: Res<ButtonMaterials>) -> ButtonBundle {
+pub fn get_menu_node(button_materials: &ButtonMaterials) -> NodeBundle {
+    NodeBundle {
+        style: Style {
+            size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+            flex_direction: FlexDirection::ColumnReverse,
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            ..Default::default()
+        },
+        material: button_materials.transparent.clone(),
+        ..Default::default()
+    }
+}
+
+pub fn get_menu_button(button_materials: &ButtonMaterials) -> ButtonBundle {
     ButtonBundle {
         style: Style {
-            size: Size::new(Val::Px(120.0), Val::Px(50.0)),
-            margin: Rect::all(Val::Auto),
+            size: Size::new(Val::Px(200.0), Val::Px(50.0)),
+            margin: Rect::all(Val::Px(5.0)),
             justify_content: JustifyContent::Center,
             align_items: AlignItems::Center,
             ..Default::default()
@@ -93,14 +132,18 @@ fn get_play_button(button_materials: Res<ButtonMaterials>) -> ButtonBundle {
     }
 }
 
-fn get_play_button_text(font: Handle<Font>) -> TextBundle {
+pub fn get_menu_button_text(label: &str, font: Handle<Font>) -> TextBundle {
+    get_menu_text(label, font, 40.0)
+}
+
+pub fn get_menu_text(label: &str, font: Handle<Font>, font_size: f32) -> TextBundle {
     TextBundle {
         text: Text {
             sections: vec![TextSection {
-                value: "Play".to_string(),
+                value: label.to_string(),
                 style: TextStyle {
-                    font: font,
-                    font_size: 40.0,
+                    font,
+                    font_size,
                     color: Color::rgb(0.9, 0.9, 0.9),
                 },
             }],
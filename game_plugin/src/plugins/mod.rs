@@ -2,9 +2,13 @@ pub mod input;
 pub mod loading;
 pub mod actions;
 pub mod menu;
+pub mod controls;
 pub mod player;
 pub mod physics;
+pub mod collisions;
+pub mod picking;
 pub mod spectator;
+pub mod character;
 pub mod console;
 
 
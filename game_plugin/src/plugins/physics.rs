@@ -1,7 +1,11 @@
 use bevy::prelude::*;
+use heron::PhysicsTime;
 use heron::prelude::*;
 
 use crate::GameState;
+use crate::plugins::console::commands::{AddConsoleCommand, ArgumentKind, CommandResult, ConsoleCommand, send_event};
+use crate::plugins::console::cvars::{AddCvar, Cvar, CvarChanged, CvarValue, Cvars};
+use crate::plugins::console::parser::{CommandLine, Value};
 
 pub struct MyPhysicsPlugin;
 
@@ -11,17 +15,376 @@ impl Plugin for MyPhysicsPlugin {
             // Heron's PhysicsPlugin, which is a wrapper for Rapier's PhysicsPlugin
             .add_plugin(PhysicsPlugin::default())
 
+            .add_event::<PhysicsEvent>()
+            .init_resource::<PhysicsSettings>()
+            .add_cvar(Cvar::new("gravity", CvarValue::Vector(PhysicsSettings::default().gravity),
+                "Acceleration of gravity, e.g. `set gravity 0 -9.81 0`"))
+            .add_cvar(Cvar::new("phys_timescale", CvarValue::Number(PhysicsSettings::default().time_scale),
+                "Speed of the physics simulation: 1 is real time, 0.5 is slow motion")
+                .range(0.0, 10.0))
+            .add_console_command(ConsoleCommand::new("physics",
+                "Show the physics settings, or pause, resume or step the simulation, e.g. `physics step 10`")
+                .optional_argument("action", ArgumentKind::String)
+                .optional_argument("frames", ArgumentKind::Integer)
+                .run(physics_command))
+            .add_console_command(ConsoleCommand::new("gravityscale",
+                "Scale the gravity of one body, e.g. `gravityscale 3v0 0.5`")
+                .argument("entity", ArgumentKind::Entity)
+                .argument("scale", ArgumentKind::Number)
+                .run(gravity_scale_command))
+            .add_console_command(ConsoleCommand::new("gravityzone",
+                "Spawn a box overriding the gravity of the bodies inside it, e.g. `gravityzone 0,5,0 5,5,5 0,9.81,0`")
+                .argument("center", ArgumentKind::Vector)
+                .argument("half_extents", ArgumentKind::Vector)
+                .argument("gravity", ArgumentKind::Vector)
+                .run(gravity_zone_command))
+            .add_system(on_cvars_changed.system().before("on_physics_event"))
+            .add_system(on_physics_event.system().label("on_physics_event"))
+            .add_system(sync_cvars.system().after("on_physics_event"))
+
             // When in the Playing GameState, gravity should be enabled!
-            // TODO | Make gravity customizable via Event!
             .add_system_set(
                 SystemSet::on_enter(GameState::Playing)
-                    .with_system(init_gravity.system())
+                    .with_system(init_physics.system())
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing)
+                    .with_system(on_physics_settings_changed.system().after("on_physics_event"))
+                    .with_system(apply_gravity_overrides.system())
             );
     }
 }
 
-/// Insert the Gravity constraint for the Physics engine
-fn init_gravity(mut commands: Commands) {
-    commands
-        .insert_resource(Gravity::from(Vec3::new(0., -9.81, 0.)));
+
+/// Event: change the physics settings, from any system (or from the console)
+#[derive(Debug, Clone, PartialEq)]
+pub enum PhysicsEvent {
+    SetGravity(Vec3),
+    /// 1 is real time
+    SetTimeScale(f32),
+    Pause,
+    Resume,
+    /// Run the paused simulation for this many frames
+    Step(u32),
+}
+
+/// The physics world's settings, applied to Heron whenever they change
+#[derive(Debug, Clone, PartialEq)]
+pub struct PhysicsSettings {
+    pub gravity: Vec3,
+    pub time_scale: f32,
+    pub is_paused: bool,
+    /// Frames left to simulate while paused
+    pub pending_steps: u32,
+}
+
+impl Default for PhysicsSettings {
+    fn default() -> Self {
+        PhysicsSettings {
+            gravity: Vec3::new(0., -9.81, 0.),
+            time_scale: 1.0,
+            is_paused: false,
+            pending_steps: 0,
+        }
+    }
+}
+
+impl PhysicsSettings {
+    pub fn apply(&mut self, event: &PhysicsEvent) {
+        match event {
+            PhysicsEvent::SetGravity(gravity) => self.gravity = *gravity,
+            PhysicsEvent::SetTimeScale(time_scale) => self.time_scale = time_scale.max(0.0),
+            PhysicsEvent::Pause => self.is_paused = true,
+            PhysicsEvent::Resume => {
+                self.is_paused = false;
+                self.pending_steps = 0;
+            },
+            PhysicsEvent::Step(frames) => {
+                self.is_paused = true;
+                self.pending_steps += frames;
+            },
+        }
+    }
+
+    /// The time scale of this frame: 0 while paused, unless stepping
+    pub fn effective_time_scale(&self) -> f32 {
+        if self.is_paused && self.pending_steps == 0 { 0.0 } else { self.time_scale }
+    }
+}
+
+
+/// Multiplies the gravity (global, or of a GravityZone) of a body, e.g. 0 for a floating body
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GravityScale(pub f32);
+
+/// A box, centered on its Transform, within which bodies are subject to its gravity instead of the global one
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GravityZone {
+    pub half_extents: Vec3,
+    pub gravity: Vec3,
+}
+
+impl GravityZone {
+    pub fn contains(&self, zone_transform: &GlobalTransform, point: Vec3) -> bool {
+        let local = zone_transform.rotation.conjugate() * (point - zone_transform.translation) / zone_transform.scale;
+        local.abs().cmple(self.half_extents).all()
+    }
+}
+
+/// Marks the bodies whose Acceleration is managed by `apply_gravity_overrides`
+struct GravityOverride;
+
+
+/// Apply the settings as soon as the game starts
+fn init_physics(mut commands: Commands, mut physics_time: ResMut<PhysicsTime>, settings: Res<PhysicsSettings>) {
+    commands.insert_resource(Gravity::from(settings.gravity));
+    physics_time.set_scale(settings.effective_time_scale());
+}
+
+/// Forward the `gravity` and `phys_timescale` cvars, as soon as they are set from the console.
+///     Only their own changes are forwarded, so that changing another cvar doesn't undo a PhysicsEvent.
+fn on_cvars_changed(
+    cvars: Res<Cvars>,
+    settings: Res<PhysicsSettings>,
+    mut cvar_changed: EventReader<CvarChanged>,
+    mut physics_events: EventWriter<PhysicsEvent>,
+) {
+    cvar_changed.iter().for_each(|CvarChanged(name)| match name.as_str() {
+        "gravity" => physics_events.send(PhysicsEvent::SetGravity(
+            cvars.vector("gravity").unwrap_or(settings.gravity))),
+        "phys_timescale" => physics_events.send(PhysicsEvent::SetTimeScale(
+            cvars.number("phys_timescale").unwrap_or(settings.time_scale))),
+        _default => {},
+    });
+}
+
+pub fn on_physics_event(mut physics_events: EventReader<PhysicsEvent>, mut settings: ResMut<PhysicsSettings>) {
+    physics_events.iter().for_each(|it| settings.apply(it));
+}
+
+/// Write the settings back to the cvars, so that `get gravity` agrees with a PhysicsEvent sent by any system
+fn sync_cvars(settings: Res<PhysicsSettings>, mut cvars: ResMut<Cvars>) {
+    if !settings.is_changed() {
+        return;
+    }
+
+    if cvars.vector("gravity") != Some(settings.gravity) {
+        if let Err(error) = cvars.set_value("gravity", CvarValue::Vector(settings.gravity)) {
+            warn!("{}", error);
+        }
+    }
+    if cvars.number("phys_timescale") != Some(settings.time_scale) {
+        if let Err(error) = cvars.set_value("phys_timescale", CvarValue::Number(settings.time_scale)) {
+            warn!("{}", error);
+        }
+    }
+}
+
+/// Apply the settings to Heron, and count the steps down while paused
+fn on_physics_settings_changed(
+    mut commands: Commands,
+    mut physics_time: ResMut<PhysicsTime>,
+    mut settings: ResMut<PhysicsSettings>,
+) {
+    if !settings.is_changed() {
+        return;
+    }
+
+    commands.insert_resource(Gravity::from(settings.gravity));
+    physics_time.set_scale(settings.effective_time_scale());
+
+    // Changing the settings again keeps this system running on the next frame, to pause after the last step
+    if settings.is_paused && settings.pending_steps > 0 {
+        settings.pending_steps -= 1;
+    }
+}
+
+/// Give each body whose gravity differs from the global one (in a GravityZone, or with a GravityScale)
+///     an Acceleration making up the difference
+fn apply_gravity_overrides(
+    mut commands: Commands,
+    settings: Res<PhysicsSettings>,
+    zones: Query<(&GlobalTransform, &GravityZone)>,
+    mut bodies: Query<
+        (Entity, &RigidBody, &GlobalTransform, Option<&GravityScale>, Option<&mut Acceleration>, Option<&GravityOverride>),
+        Without<GravityZone>,
+    >,
+) {
+    bodies.iter_mut().for_each(|(entity, body, transform, gravity_scale, acceleration, gravity_override)| {
+        if *body != RigidBody::Dynamic {
+            return;
+        }
+
+        let zone_gravity = zones.iter()
+            .find(|(zone_transform, zone)| zone.contains(zone_transform, transform.translation))
+            .map(|(_, zone)| zone.gravity);
+        let difference = effective_gravity(settings.gravity, zone_gravity, gravity_scale.map(|it| it.0))
+            - settings.gravity;
+
+        match (acceleration, gravity_override) {
+            (Some(mut acceleration), Some(_)) => if acceleration.linear != difference {
+                acceleration.linear = difference;
+            },
+            (None, _) if difference != Vec3::ZERO => {
+                commands.entity(entity)
+                    .insert(Acceleration::from_linear(difference))
+                    .insert(GravityOverride);
+            },
+            // Other Accelerations belong to someone else
+            _default => {},
+        }
+    });
+}
+
+
+/// Commands
+/// `physics [pause | resume | step [frames]]`
+fn physics_command(world: &mut World, command: &CommandLine) -> CommandResult {
+    let action = command.argument(0).map(|it| it.to_string().to_lowercase());
+    let event = match action.as_deref() {
+        None | Some("status") => {
+            let settings = world.get_resource::<PhysicsSettings>()
+                .ok_or_else(|| "Physics is not set up".to_string())?;
+            return Ok(format!("Gravity {}, time scale {}{}", settings.gravity, settings.time_scale,
+                if settings.is_paused { ", paused" } else { "" }));
+        },
+        Some("pause") => PhysicsEvent::Pause,
+        Some("resume") => PhysicsEvent::Resume,
+        Some("step") => PhysicsEvent::Step(command.argument(1).and_then(Value::as_i64).unwrap_or(1).max(1) as u32),
+        Some(other) => return Err(format!("Unknown action '{}': expected status, pause, resume or step", other).into()),
+    };
+
+    let message = format!("{:?}", event);
+    send_event(world, event)?;
+    Ok(message)
+}
+
+/// `gravityscale <entity> <scale>`
+fn gravity_scale_command(world: &mut World, command: &CommandLine) -> CommandResult {
+    let entity = command.require(0, "an entity id", Value::as_entity)?;
+    let scale = command.require(1, "a number", Value::as_f32)?;
+
+    let mut entity_mut = world.get_entity_mut(entity)
+        .ok_or_else(|| format!("No entity {}", Value::Entity(entity)))?;
+    entity_mut.insert(GravityScale(scale));
+    Ok(format!("Gravity scale of {} set to {}", Value::Entity(entity), scale))
+}
+
+/// `gravityzone <center> <half extents> <gravity>`: remove it with `despawn`
+fn gravity_zone_command(world: &mut World, command: &CommandLine) -> CommandResult {
+    let center = command.require(0, "a vector", Value::as_vec3)?;
+    let half_extents = command.require(1, "a vector", Value::as_vec3)?;
+    let gravity = command.require(2, "a vector", Value::as_vec3)?;
+
+    let zone = world.spawn()
+        .insert_bundle((Transform::from_translation(center), GlobalTransform::from_translation(center)))
+        .insert(GravityZone { half_extents: half_extents.abs(), gravity })
+        .id();
+    Ok(format!("Spawned gravity zone {}", Value::Entity(zone)))
+}
+
+
+/// Helpers
+/// The gravity of a body: of the zone it is in, if any, times its scale
+pub fn effective_gravity(global: Vec3, zone: Option<Vec3>, scale: Option<f32>) -> Vec3 {
+    zone.unwrap_or(global) * scale.unwrap_or(1.0)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use bevy::app::Events;
+    use bevy::prelude::*;
+
+    use crate::plugins::console::cvars::{Cvar, CvarChanged, CvarValue, Cvars};
+    use super::{GravityZone, PhysicsEvent, PhysicsSettings, effective_gravity, on_cvars_changed, on_physics_event,
+        sync_cvars};
+
+    #[test]
+    fn events_update_the_settings() {
+        let mut app = App::build();
+        app
+            .add_event::<PhysicsEvent>()
+            .init_resource::<PhysicsSettings>()
+            .add_system(on_physics_event.system());
+        let mut app = app.app;
+
+        let mut events = app.world.get_resource_mut::<Events<PhysicsEvent>>().unwrap();
+        events.send(PhysicsEvent::SetGravity(Vec3::new(0.0, -1.62, 0.0)));
+        events.send(PhysicsEvent::SetTimeScale(0.5));
+        events.send(PhysicsEvent::Step(3));
+        app.update();
+
+        let settings = app.world.get_resource::<PhysicsSettings>().unwrap();
+        assert_eq!(settings.gravity, Vec3::new(0.0, -1.62, 0.0));
+        assert!(settings.is_paused);
+        assert_eq!(settings.pending_steps, 3);
+        assert_eq!(settings.effective_time_scale(), 0.5);
+    }
+
+    #[test]
+    fn cvars_follow_the_physics_events() {
+        let mut cvars = Cvars::default();
+        cvars.register(Cvar::new("gravity", CvarValue::Vector(Vec3::new(0.0, -9.81, 0.0)), ""));
+        cvars.register(Cvar::new("phys_timescale", CvarValue::Number(1.0), ""));
+        cvars.register(Cvar::new("fov", CvarValue::Number(70.0), ""));
+
+        let mut app = App::build();
+        app
+            .add_event::<PhysicsEvent>()
+            .add_event::<CvarChanged>()
+            .insert_resource(cvars)
+            .init_resource::<PhysicsSettings>()
+            .add_system(on_cvars_changed.system().before("on_physics_event"))
+            .add_system(on_physics_event.system().label("on_physics_event"))
+            .add_system(sync_cvars.system().after("on_physics_event"));
+        let mut app = app.app;
+        let earth = Vec3::new(0.0, -9.81, 0.0);
+        let moon = Vec3::new(0.0, -1.62, 0.0);
+        let gravity = |app: &App| app.world.get_resource::<PhysicsSettings>().unwrap().gravity;
+
+        // Code sets the gravity and time scale: the cvars reflect them, even once another cvar changes
+        let mut events = app.world.get_resource_mut::<Events<PhysicsEvent>>().unwrap();
+        events.send(PhysicsEvent::SetGravity(moon));
+        events.send(PhysicsEvent::SetTimeScale(0.5));
+        app.update();
+        app.world.get_resource_mut::<Events<CvarChanged>>().unwrap().send(CvarChanged("fov".to_string()));
+        app.update();
+        let cvars = app.world.get_resource::<Cvars>().unwrap();
+        assert_eq!(cvars.vector("gravity"), Some(moon));
+        assert_eq!(cvars.number("phys_timescale"), Some(0.5));
+        assert_eq!(gravity(&app), moon);
+
+        // Setting the gravity cvar applies it
+        app.world.get_resource_mut::<Cvars>().unwrap().set_value("gravity", CvarValue::Vector(earth)).unwrap();
+        app.world.get_resource_mut::<Events<CvarChanged>>().unwrap().send(CvarChanged("gravity".to_string()));
+        app.update();
+        assert_eq!(gravity(&app), earth);
+    }
+
+    #[test]
+    fn pausing_stops_time_until_resumed() {
+        let mut settings = PhysicsSettings::default();
+
+        settings.apply(&PhysicsEvent::Pause);
+        assert_eq!(settings.effective_time_scale(), 0.0);
+
+        settings.apply(&PhysicsEvent::Resume);
+        assert_eq!(settings.effective_time_scale(), 1.0);
+    }
+
+    #[test]
+    fn zones_and_scales_override_the_gravity() {
+        let global = Vec3::new(0.0, -9.81, 0.0);
+        let zone = GravityZone { half_extents: Vec3::new(1.0, 2.0, 1.0), gravity: Vec3::new(0.0, 9.81, 0.0) };
+        let zone_transform = GlobalTransform::from_translation(Vec3::new(0.0, 10.0, 0.0));
+
+        assert!(zone.contains(&zone_transform, Vec3::new(0.5, 11.5, -1.0)));
+        assert!(!zone.contains(&zone_transform, Vec3::new(0.0, 7.0, 0.0)));
+
+        assert_eq!(effective_gravity(global, None, None), global);
+        assert_eq!(effective_gravity(global, Some(zone.gravity), None), zone.gravity);
+        assert_eq!(effective_gravity(global, Some(zone.gravity), Some(0.5)), zone.gravity * 0.5);
+        assert_eq!(effective_gravity(global, None, Some(0.0)), Vec3::ZERO);
+    }
 }
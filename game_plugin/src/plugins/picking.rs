@@ -0,0 +1,269 @@
+/// Picking: what the SpectatorCamera is pointing at, found by casting a ray into heron's world.
+///     Hovering goes through the crosshair while the cursor is grabbed, and through the cursor otherwise;
+///     aiming (`look`, spawning) always goes through the crosshair.
+use bevy::prelude::*;
+use bevy::render::camera::Camera;
+use heron::prelude::*;
+use heron::rapier_plugin::PhysicsWorld;
+
+use crate::GameState;
+use crate::plugins::collisions::Trigger;
+use crate::plugins::console::commands::{AddConsoleCommand, CommandResult, ConsoleCommand};
+use crate::plugins::console::cvars::{AddCvar, Cvar, CvarValue, Cvars};
+use crate::plugins::console::entities;
+use crate::plugins::console::parser::CommandLine;
+use crate::plugins::input::cursor::CursorGrab;
+use crate::plugins::player::CubeActor;
+use crate::plugins::spectator::SpectatorCamera;
+
+
+/// How much lighter a hovered cube gets, from 0 (unchanged) to 1 (white)
+const HIGHLIGHT_BLEND: f32 = 0.5;
+
+
+/// Plugins
+pub struct PickingPlugin;
+
+impl Plugin for PickingPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app
+            .init_resource::<PickingSettings>()
+            .init_resource::<Hovered>()
+            .init_resource::<Aimed>()
+            .add_event::<HoverStarted>()
+            .add_event::<HoverEnded>()
+            .add_cvar(Cvar::new("pick_distance", CvarValue::Number(PickingSettings::default().max_distance),
+                "How far away things can be pointed at, in units")
+                .range(1.0, 10000.0))
+            .add_console_command(ConsoleCommand::new("look", "Show what is under the crosshair")
+                .run(look_command))
+            .add_system(apply_cvars.system())
+            .add_system_set(SystemSet::on_update(GameState::Playing)
+                .with_system(update_hovered.system().label(PickingSystem::Update))
+                .with_system(highlight_hovered_cubes.system().after(PickingSystem::Update)));
+    }
+}
+
+#[derive(SystemLabel, Debug, Clone, PartialEq, Eq, Hash)]
+pub enum PickingSystem {
+    Update,
+}
+
+
+/// Picking configuration, driven by the `pick_distance` cvar
+#[derive(Debug, Clone)]
+pub struct PickingSettings {
+    /// Units from the camera
+    pub max_distance: f32,
+}
+
+impl Default for PickingSettings {
+    fn default() -> Self {
+        PickingSettings { max_distance: 100.0 }
+    }
+}
+
+
+/// Where the ray hit
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PickHit {
+    pub entity: Entity,
+    pub point: Vec3,
+    pub normal: Vec3,
+    /// Units from the camera
+    pub distance: f32,
+}
+
+impl PickHit {
+    /// Where to put something of the given half height so that it rests against the hit surface
+    pub fn resting_point(&self, half_height: f32) -> Vec3 {
+        self.point + self.normal * half_height
+    }
+}
+
+/// Resource: what the SpectatorCamera is pointing at, updated every frame
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Hovered(pub Option<PickHit>);
+
+/// Resource: what is under the crosshair, wherever the cursor is, updated every frame
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Aimed(pub Option<PickHit>);
+
+/// Event: the SpectatorCamera started pointing at an entity
+pub struct HoverStarted(pub Entity);
+
+/// Event: the SpectatorCamera stopped pointing at an entity, which may have been despawned since
+pub struct HoverEnded(pub Entity);
+
+/// The original color of a hovered CubeActor, restored once it's no longer hovered
+struct Highlighted {
+    original: Color,
+}
+
+
+/// Systems
+fn apply_cvars(cvars: Res<Cvars>, mut settings: ResMut<PickingSettings>) {
+    if !cvars.is_changed() {
+        return;
+    }
+
+    settings.max_distance = cvars.number("pick_distance").unwrap_or(settings.max_distance);
+}
+
+/// Cast rays from the SpectatorCamera, through the crosshair and the cursor
+#[allow(clippy::too_many_arguments)]
+fn update_hovered(
+    physics_world: PhysicsWorld,
+    settings: Res<PickingSettings>,
+    cursor_grab: Res<CursorGrab>,
+    windows: Res<Windows>,
+    cameras: Query<(Entity, &Camera, &GlobalTransform, Option<&Parent>), With<SpectatorCamera>>,
+    triggers: Query<(), With<Trigger>>,
+    mut hovered: ResMut<Hovered>,
+    mut aimed: ResMut<Aimed>,
+    mut hover_started: EventWriter<HoverStarted>,
+    mut hover_ended: EventWriter<HoverEnded>,
+) {
+    let camera = cameras.iter().next();
+    let pick = |ndc: Vec2| camera.and_then(|(camera_entity, camera, transform, parent)| {
+        // Neither the camera nor the body it is the eye of are in the way, and triggers can't be seen
+        let body = parent.map(|it| it.0);
+        let (origin, direction) = screen_ray(transform, camera.projection_matrix, ndc);
+        physics_world.ray_cast_with_filter(
+            origin,
+            direction * settings.max_distance,
+            true,
+            CollisionLayers::default(),
+            |it| it != camera_entity && Some(it) != body && triggers.get(it).is_err(),
+        ).map(|it| PickHit {
+            entity: it.entity,
+            point: it.collision_point,
+            normal: it.normal,
+            distance: (it.collision_point - origin).length(),
+        })
+    });
+
+    let crosshair_hit = pick(Vec2::ZERO);
+    let hit = match cursor_grab.0 {
+        true => crosshair_hit,
+        false => camera
+            .and_then(|(_, camera, _, _)| windows.get(camera.window))
+            .and_then(|window| {
+                let size = Vec2::new(window.width(), window.height());
+                window.cursor_position().map(|it| it / size * 2.0 - Vec2::ONE)
+            })
+            .and_then(pick),
+    };
+    if aimed.0 != crosshair_hit {
+        aimed.0 = crosshair_hit;
+    }
+
+    let previous = hovered.0.map(|it| it.entity);
+    let current = hit.map(|it| it.entity);
+    if previous != current {
+        previous.into_iter().for_each(|it| hover_ended.send(HoverEnded(it)));
+        current.into_iter().for_each(|it| hover_started.send(HoverStarted(it)));
+    }
+    // Only write on change, so that `Res<Hovered>::is_changed` means something
+    if hovered.0 != hit {
+        hovered.0 = hit;
+    }
+}
+
+/// Lighten the CubeActor being pointed at
+fn highlight_hovered_cubes(
+    mut commands: Commands,
+    mut hover_started: EventReader<HoverStarted>,
+    mut hover_ended: EventReader<HoverEnded>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    cubes: Query<(&Handle<StandardMaterial>, Option<&Highlighted>), With<CubeActor>>,
+) {
+    hover_ended.iter().for_each(|HoverEnded(entity)| {
+        if let Ok((material, Some(highlighted))) = cubes.get(*entity) {
+            if let Some(material) = materials.get_mut(material) {
+                material.base_color = highlighted.original;
+            }
+            commands.entity(*entity).remove::<Highlighted>();
+        }
+    });
+
+    hover_started.iter().for_each(|HoverStarted(entity)| {
+        if let Ok((material, None)) = cubes.get(*entity) {
+            if let Some(material) = materials.get_mut(material) {
+                commands.entity(*entity).insert(Highlighted { original: material.base_color });
+                material.base_color = highlight(material.base_color);
+            }
+        }
+    });
+}
+
+
+/// Commands
+/// `look`: the entity under the crosshair, where it was hit, and how far away it is
+fn look_command(world: &mut World, _command: &CommandLine) -> CommandResult {
+    let hit = world.get_resource::<Aimed>().and_then(|it| it.0);
+    match hit {
+        Some(hit) => Ok(format!("{}, hit at {} ({:.2} units away), normal {}",
+            entities::describe(world, hit.entity), hit.point, hit.distance, hit.normal)),
+        None => Ok("Nothing under the crosshair".to_string()),
+    }
+}
+
+
+/// Helpers
+/// The ray through a point of the screen, in normalized device coordinates (-1 to 1, Y up):
+///     its origin on the near plane, and its direction
+pub fn screen_ray(camera_transform: &GlobalTransform, projection: Mat4, ndc: Vec2) -> (Vec3, Vec3) {
+    let ndc_to_world = camera_transform.compute_matrix() * projection.inverse();
+    let unproject = |depth: f32| {
+        let point = ndc_to_world * ndc.extend(depth).extend(1.0);
+        point.truncate() / point.w
+    };
+
+    let origin = unproject(0.0);
+    (origin, (unproject(0.5) - origin).normalize())
+}
+
+/// Blend a color towards white, keeping its alpha
+fn highlight(color: Color) -> Color {
+    let blend = |it: f32| it + (1.0 - it) * HIGHLIGHT_BLEND;
+    Color::rgba(blend(color.r()), blend(color.g()), blend(color.b()), color.a())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+
+    use super::{highlight, screen_ray};
+
+    fn assert_near(actual: Vec3, expected: Vec3) {
+        assert!((actual - expected).length() < 1e-4, "expected {:?}, got {:?}", expected, actual);
+    }
+
+    #[test]
+    fn rays_go_through_the_screen() {
+        let projection = Mat4::perspective_rh(90f32.to_radians(), 1.0, 0.1, 1000.0);
+        let camera = GlobalTransform::from_xyz(0.0, 2.0, 0.0);
+
+        // The crosshair looks straight ahead, from the near plane
+        let (origin, direction) = screen_ray(&camera, projection, Vec2::ZERO);
+        assert_near(origin, Vec3::new(0.0, 2.0, -0.1));
+        assert_near(direction, -Vec3::Z);
+
+        // The right edge of a 90° field of view is 45° to the right
+        let (_, direction) = screen_ray(&camera, projection, Vec2::new(1.0, 0.0));
+        assert_near(direction, Vec3::new(1.0, 0.0, -1.0).normalize());
+
+        // Turning the camera turns the ray
+        let camera = GlobalTransform::from(Transform::from_xyz(0.0, 2.0, 0.0).looking_at(Vec3::ZERO, Vec3::Z));
+        let (_, direction) = screen_ray(&camera, projection, Vec2::ZERO);
+        assert_near(direction, -Vec3::Y);
+    }
+
+    #[test]
+    fn highlights_are_lighter() {
+        let color = highlight(Color::rgba(0.0, 0.5, 1.0, 0.8));
+        assert_eq!((color.r(), color.g(), color.b(), color.a()), (0.5, 0.75, 1.0, 0.8));
+    }
+}
@@ -2,6 +2,11 @@ use bevy::prelude::*;
 use heron::prelude::*;
 
 use crate::GameState;
+use crate::plugins::collisions::CollisionGroup;
+use crate::plugins::console::commands::{AddConsoleCommand, ArgumentKind, ConsoleCommand, send_event};
+use crate::plugins::console::parser::{ArgumentError, CommandLine, Value};
+use crate::plugins::picking::Aimed;
+use crate::utils::colliders::{MeshCollider, mesh_collision_shape};
 use crate::utils::random_color;
 use super::actions;
 
@@ -20,6 +25,26 @@ pub struct PlayerPlugin;
 impl Plugin for PlayerPlugin {
     fn build(&self, app: &mut AppBuilder) {
         app
+            .add_console_command(ConsoleCommand::new("spawn",
+                "Spawn a cube, e.g. `spawn cube 0 10 0 color=red`, or where you are looking without a position")
+                .argument("kind", ArgumentKind::String)
+                .optional_argument("position", ArgumentKind::Position)
+                .named_argument("color", ArgumentKind::Color)
+                .run(|world, command| {
+                    let mut event = spawn_cube_actor_command(command)?;
+                    // Resting against whatever is under the crosshair
+                    if let (None, Some(hit)) = (command.argument(1), world.get_resource::<Aimed>().and_then(|it| it.0)) {
+                        event.position = hit.resting_point(shape::Cube::default().size / 2.0);
+                    }
+                    let message = format!("Spawning Cube Actor at {}...", event.position);
+                    send_event(world, event)?;
+                    Ok(message)
+                }))
+            .add_console_command(ConsoleCommand::new("spawncubeactor", "Spawn a cube, with a random color")
+                .run(|world, _| {
+                    send_event(world, actions::SpawnCubeActor::default())?;
+                    Ok("Spawning Cube Actor...".to_string())
+                }))
             .add_system_set(
                 SystemSet::on_enter(GameState::Playing)
                     .with_system(spawn_light.system())
@@ -44,19 +69,40 @@ fn spawn_cube_actor_listener(
     mut spawn_cube_actor_event: EventReader<actions::SpawnCubeActor>
 ) {
     spawn_cube_actor_event.iter()
-        .for_each(|_it| {
+        .for_each(|it| {
+            let color = it.color.unwrap_or_else(random_color::get_random_color);
+            let mesh = Mesh::from(shape::Cube::default());
+            let transform = Transform::from_translation(it.position);
+            let collision_shape = mesh_collision_shape(&mesh, MeshCollider::Cuboid, transform.scale)
+                .expect("bevy's cube has vertex positions");
             commands.spawn_bundle(PbrBundle {
-                mesh: meshes.add(Mesh::from(shape::Cube::default())),
-                material: materials.add(random_color::get_random_color().into()),
-                transform: Transform::from_xyz(0.0, 0.5, 0.0),
+                mesh: meshes.add(mesh),
+                material: materials.add(color.into()),
+                transform,
                 ..Default::default()
             })
-                .insert(CollisionShape::Sphere { radius: 1.0 })
+                .insert(collision_shape)
                 .insert(RigidBody::Dynamic)
+                .insert(CollisionGroup::new("Actor"))
                 .insert(CubeActor);
         });
 }
 
+/// `spawn cube [x y z | x,y,z] [color=<name or hex>]`
+fn spawn_cube_actor_command(command: &CommandLine) -> Result<actions::SpawnCubeActor, ArgumentError> {
+    command.require(0, "'cube'", |it| match it {
+        Value::String(kind) if kind.eq_ignore_ascii_case("cube") => Some(()),
+        _default => None,
+    })?;
+
+    let mut event = actions::SpawnCubeActor::default();
+    if command.argument(1).is_some() {
+        event.position = command.vec3_at(1)?;
+    }
+    event.color = command.optional("color", "a color name or hex code", Value::as_color)?;
+    Ok(event)
+}
+
 /// To query lights, use `bevy_pbr::Light`
 fn spawn_light(mut commands: Commands) {
     commands
@@ -71,16 +117,22 @@ fn spawn_floor(mut commands: Commands) {
     commands
         .spawn_bundle((Transform::identity(), GlobalTransform::identity()))
         .insert(RigidBody::Static)
-        .insert(CollisionShape::HeightField {
-            size: Vec2::new(50., 50.),
-            heights: vec![
-                vec![1.5, 0.8, 0., 0., 3.0],
-                vec![0.8, 0.2, 0., 0., 3.0],
-                vec![0., 0.5, 0., 0., 3.0],
-                vec![0., 0., 0.6, 0., 3.0],
-                vec![3., 3., 3., 3., 3.0],
-            ],
-        });
+        .insert(floor_shape())
+        .insert(CollisionGroup::new("World"));
+}
+
+/// The floor's heightfield, flat around the origin
+pub fn floor_shape() -> CollisionShape {
+    CollisionShape::HeightField {
+        size: Vec2::new(50., 50.),
+        heights: vec![
+            vec![1.5, 0.8, 0., 0., 3.0],
+            vec![0.8, 0.2, 0., 0., 3.0],
+            vec![0., 0.5, 0., 0., 3.0],
+            vec![0., 0., 0.6, 0., 3.0],
+            vec![3., 3., 3., 3., 3.0],
+        ],
+    }
 }
 
 
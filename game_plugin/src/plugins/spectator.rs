@@ -6,8 +6,11 @@ use crate::GameState;
 use crate::plugins::actions::{
     MoveForward, StrafeRight, LookUp, LookRight, Crouch, Jump, SpawnSpectatorCamera
 };
+use crate::plugins::console::commands::{AddConsoleCommand, ConsoleCommand};
+use crate::plugins::console::cvars::{AddCvar, Cvar, CvarValue, Cvars};
+use crate::plugins::character::{CharacterController, CharacterEye};
 use crate::plugins::player::Possessed;
-use crate::plugins::console::IsFocusedOnUI;
+use crate::plugins::input::{FrameTime, InputContext, InputContexts};
 
 
 /// External-facing Plugin. Use this to add to your project!
@@ -28,7 +31,40 @@ impl Plugin for SpectatorPlugin {
     fn build(&self, app: &mut AppBuilder) {
         const GAME_STATE: GameState = GameState::Playing;
 
-        app.add_system_set(SystemSet::on_enter(GAME_STATE)
+        let look_settings = LookSettings::default();
+        app.init_resource::<LookSettings>()
+            .init_resource::<SpectatorSettings>()
+            .add_cvar(Cvar::new("cam_speed", CvarValue::Number(SpectatorSettings::default().speed),
+                "Spectator camera speed, in units per second")
+                .range(0.0, 1000.0)
+                .persistent())
+            .add_cvar(Cvar::new("look_yaw_sensitivity", CvarValue::Number(look_settings.yaw_sensitivity),
+                "Horizontal look speed, in degrees per mouse count")
+                .range(0.001, 10.0)
+                .persistent())
+            .add_cvar(Cvar::new("look_pitch_sensitivity", CvarValue::Number(look_settings.pitch_sensitivity),
+                "Vertical look speed, in degrees per mouse count")
+                .range(0.001, 10.0)
+                .persistent())
+            .add_cvar(Cvar::new("look_invert_y", CvarValue::Bool(look_settings.invert_y),
+                "Invert the vertical look axis")
+                .persistent())
+            .add_cvar(Cvar::new("look_acceleration", CvarValue::Number(look_settings.acceleration),
+                "Extra look sensitivity per 1000 mouse counts per second")
+                .range(0.0, 10.0)
+                .persistent())
+            .add_cvar(Cvar::new("look_smoothing", CvarValue::Number(look_settings.smoothing),
+                "Look smoothing time constant, in seconds")
+                .range(0.0, 1.0)
+                .persistent())
+            .add_cvar(Cvar::new("look_pitch_limit", CvarValue::Number(look_settings.max_pitch),
+                "Maximum pitch above or below the horizon, in degrees")
+                .range(0.0, 89.9)
+                .persistent())
+            .add_system(apply_cvars.system())
+            .add_console_command(ConsoleCommand::new("spectator", "Spawn and possess a Spectator Camera")
+                .send_event(|_| Ok(SpawnSpectatorCamera)))
+            .add_system_set(SystemSet::on_enter(GAME_STATE)
                 .with_system(on_enter.system())
                 .with_system(create_spectator_camera.system()))
 
@@ -41,8 +77,7 @@ impl Plugin for SpectatorPlugin {
                 .with_system(on_update_move_strafe.system())
                 .with_system(on_update_crouch.system())
                 .with_system(on_update_jump.system())
-                .with_system(on_update_look_up.system())
-                .with_system(on_update_look_right.system()))
+                .with_system(on_update_look.system()))
 
             .add_system_set(SystemSet::on_exit(GAME_STATE)
                 .with_system(on_exit.system()));
@@ -64,11 +99,12 @@ pub struct SpectatorCamera;
 fn on_spawn_spectator_camera_listener(
     mut commands: Commands,
     mut spawn_spectator_camera: EventReader<SpawnSpectatorCamera>,
-    existing_cameras: Query<Entity, (With<Possessed>, With<SpectatorCamera>)>
+    mut input_contexts: ResMut<InputContexts>,
+    existing_cameras: Query<Entity, With<SpectatorCamera>>
 ) {
     spawn_spectator_camera.iter()
         .for_each(|it| {
-            // Spawn if there is no already-possessed SpectatorCamera
+            // Spawn if there is no SpectatorCamera, even as the eye of a walking character
             if let Err(QuerySingleError::NoEntities(_)) = existing_cameras.single() {
                 info!("Spawning Spectator Camera...");
                 commands
@@ -79,8 +115,9 @@ fn on_spawn_spectator_camera_listener(
                     })
                     .insert(Possessed)
                     .insert(SpectatorCamera);
+                input_contexts.push(InputContext::Spectator);
             } else {
-                info!("Possessed Spectator Camera already exists! Ignoring spawn attempt...");
+                info!("Spectator Camera already exists! Ignoring spawn attempt...");
             }
         });
 }
@@ -95,19 +132,83 @@ fn create_spectator_camera(
 
 
 
-// TODO | Promote these to configurations of some kind!
-//  Maybe it can be a proprety of Possessed? Or Character?
-const SPEED: f32 = 15.0;
-const SENSITIVITY: f32 = 10.0;
+/// Flying configuration, driven by the `cam_speed` cvar
+#[derive(Debug, Clone)]
+pub struct SpectatorSettings {
+    /// Units per second
+    pub speed: f32,
+}
+
+impl Default for SpectatorSettings {
+    fn default() -> Self {
+        SpectatorSettings { speed: 15.0 }
+    }
+}
+
+
+/// Look controller configuration, for the mouse as well as the sticks
+#[derive(Debug, Clone)]
+pub struct LookSettings {
+    /// Degrees per mouse count
+    pub yaw_sensitivity: f32,
+    pub pitch_sensitivity: f32,
+
+    pub invert_y: bool,
+
+    /// Extra sensitivity per 1000 mouse counts per second, so that fast motions turn further. 0 disables it.
+    pub acceleration: f32,
+
+    /// Time constant, in seconds, of the smoothing applied to the look motion. 0 disables it.
+    pub smoothing: f32,
+
+    /// Pitch limits, in degrees from the horizon, so that the camera can't flip upside down
+    pub min_pitch: f32,
+    pub max_pitch: f32,
+}
+
+impl Default for LookSettings {
+    fn default() -> Self {
+        LookSettings {
+            yaw_sensitivity: 0.1,
+            pitch_sensitivity: 0.1,
+            invert_y: false,
+            acceleration: 0.0,
+            smoothing: 0.0,
+            min_pitch: -89.0,
+            max_pitch: 89.0,
+        }
+    }
+}
+
+/// Keep the settings in sync with the cvars, as soon as they are set from the console
+fn apply_cvars(
+    cvars: Res<Cvars>,
+    mut spectator_settings: ResMut<SpectatorSettings>,
+    mut look_settings: ResMut<LookSettings>,
+) {
+    if !cvars.is_changed() {
+        return;
+    }
+
+    spectator_settings.speed = cvars.number("cam_speed").unwrap_or(spectator_settings.speed);
+    look_settings.yaw_sensitivity = cvars.number("look_yaw_sensitivity").unwrap_or(look_settings.yaw_sensitivity);
+    look_settings.pitch_sensitivity = cvars.number("look_pitch_sensitivity").unwrap_or(look_settings.pitch_sensitivity);
+    look_settings.invert_y = cvars.bool("look_invert_y").unwrap_or(look_settings.invert_y);
+    look_settings.acceleration = cvars.number("look_acceleration").unwrap_or(look_settings.acceleration);
+    look_settings.smoothing = cvars.number("look_smoothing").unwrap_or(look_settings.smoothing);
+    look_settings.max_pitch = cvars.number("look_pitch_limit").unwrap_or(look_settings.max_pitch);
+    look_settings.min_pitch = -look_settings.max_pitch;
+}
 
 fn on_update_move_forward(
-    mut player_query: Query<&mut Transform, (With<Possessed>, Without<IsFocusedOnUI>)>,
+    mut player_query: Query<&mut Transform, (With<Possessed>, Without<CharacterController>)>,
     mut actions: EventReader<MoveForward>,
-    time: Res<Time>
+    spectator_settings: Res<SpectatorSettings>,
+    frame_time: Res<FrameTime>
 ) {
     actions.iter().for_each(|action: &MoveForward| {
         player_query.iter_mut().for_each(|mut transform| {
-            let delta = -1.0 * transform.local_z() * SPEED * action.0 * time.delta_seconds();
+            let delta = -1.0 * transform.local_z() * spectator_settings.speed * action.0 * frame_time.delta_seconds;
             let new_position = transform.translation + delta;
             transform.translation = new_position;
         });
@@ -115,13 +216,14 @@ fn on_update_move_forward(
 }
 
 fn on_update_move_strafe(
-    mut player_query: Query<&mut Transform, (With<Possessed>, Without<IsFocusedOnUI>)>,
+    mut player_query: Query<&mut Transform, (With<Possessed>, Without<CharacterController>)>,
     mut actions: EventReader<StrafeRight>,
-    time: Res<Time>
+    spectator_settings: Res<SpectatorSettings>,
+    frame_time: Res<FrameTime>
 ) {
     actions.iter().for_each(|action: &StrafeRight| {
         player_query.iter_mut().for_each(|mut transform| {
-            let delta = transform.local_x() * SPEED * action.0 * time.delta_seconds();
+            let delta = transform.local_x() * spectator_settings.speed * action.0 * frame_time.delta_seconds;
             let new_position = transform.translation + delta;
             transform.translation = new_position;
         });
@@ -129,15 +231,16 @@ fn on_update_move_strafe(
 }
 
 fn on_update_crouch(
-    mut player_query: Query<&mut Transform, (With<Possessed>, Without<IsFocusedOnUI>)>,
+    mut player_query: Query<&mut Transform, (With<Possessed>, Without<CharacterController>)>,
     mut actions: EventReader<Crouch>,
-    time: Res<Time>
+    spectator_settings: Res<SpectatorSettings>,
+    frame_time: Res<FrameTime>
 ) {
     actions.iter()
         .filter(|action| action.0)
         .for_each(|action: &Crouch| {
             player_query.iter_mut().for_each(|mut transform| {
-                let delta = -Vec3::Y * SPEED * time.delta_seconds();
+                let delta = -Vec3::Y * spectator_settings.speed * frame_time.delta_seconds;
                 let new_position = transform.translation + delta;
                 transform.translation = new_position;
             });
@@ -145,53 +248,285 @@ fn on_update_crouch(
 }
 
 fn on_update_jump(
-    mut player_query: Query<&mut Transform, (With<Possessed>, Without<IsFocusedOnUI>)>,
+    mut player_query: Query<&mut Transform, (With<Possessed>, Without<CharacterController>)>,
     mut actions: EventReader<Jump>,
-    time: Res<Time>
+    spectator_settings: Res<SpectatorSettings>,
+    frame_time: Res<FrameTime>
 ) {
     actions.iter()
         .filter(|action| action.0)
         .for_each(|action: &Jump| {
             player_query.iter_mut().for_each(|mut transform| {
-                let delta = Vec3::Y * SPEED * time.delta_seconds();
+                let delta = Vec3::Y * spectator_settings.speed * frame_time.delta_seconds;
                 let new_position = transform.translation + delta;
                 transform.translation = new_position;
             });
         });
 }
 
-fn on_update_look_up(
-    mut player_query: Query<&mut Transform, (With<Possessed>, Without<IsFocusedOnUI>)>,
-    mut actions: EventReader<LookUp>,
-    time: Res<Time>
+/// Turn the Possessed camera: yaw around the world's vertical axis, and pitch within the LookSettings' limits
+#[allow(clippy::too_many_arguments)]
+fn on_update_look(
+    mut player_query: Query<(&mut Transform, Option<&Children>), With<Possessed>>,
+    mut eyes: Query<&mut Transform, (With<CharacterEye>, Without<Possessed>)>,
+    eye_markers: Query<(), With<CharacterEye>>,
+    mut look_up: EventReader<LookUp>,
+    mut look_right: EventReader<LookRight>,
+    look_settings: Res<LookSettings>,
+    frame_time: Res<FrameTime>,
+    mut smoothed_rate: Local<Vec2>,
 ) {
-    actions.iter().for_each(|action: &LookUp| {
-        let delta: f32 = (action.0 * time.delta_seconds() * SENSITIVITY).to_radians();
-        player_query.iter_mut().for_each(|mut transform| {
-            let new_rotation = Quat::from_axis_angle(
-                -transform.local_x(),
-                delta
-            ) * transform.rotation;
+    let delta_seconds = frame_time.delta_seconds;
+    if delta_seconds <= 0.0 {
+        return;
+    }
 
-            transform.rotation = new_rotation.normalize();
-        });
+    // Motion of this frame, in mouse counts
+    let motion = Vec2::new(
+        look_right.iter().map(|it| it.0).sum(),
+        look_up.iter().map(|it| it.0).sum(),
+    );
+
+    // Smooth and accelerate the rate of motion, rather than the motion itself, to stay framerate-independent
+    let rate = motion / delta_seconds;
+    *smoothed_rate = if look_settings.smoothing > 0.0 {
+        let blend = 1.0 - (-delta_seconds / look_settings.smoothing).exp();
+        *smoothed_rate + (rate - *smoothed_rate) * blend
+    } else {
+        rate
+    };
+    // Smoothing only ever approaches zero, so snap to it once the motion is imperceptible
+    if smoothed_rate.length_squared() < 1e-6 {
+        *smoothed_rate = Vec2::ZERO;
+        return;
+    }
+
+    let acceleration = 1.0 + look_settings.acceleration * smoothed_rate.length() / 1000.0;
+    let motion = *smoothed_rate * acceleration * delta_seconds;
+
+    let yaw_delta = -motion.x * look_settings.yaw_sensitivity;
+    let pitch_delta = -motion.y * look_settings.pitch_sensitivity
+        * if look_settings.invert_y { -1.0 } else { 1.0 };
+
+    player_query.iter_mut().for_each(|(mut transform, children)| {
+        let eye = children
+            .and_then(|children| children.iter().copied().find(|&it| eye_markers.get(it).is_ok()));
+
+        match eye {
+            // A walking character only yaws, so that its capsule stays upright: its eye pitches
+            Some(eye) => {
+                if let Ok(mut eye_transform) = eyes.get_mut(eye) {
+                    let pitch_delta = clamp_pitch_delta(&eye_transform, pitch_delta, &look_settings);
+                    eye_transform.rotation = (Quat::from_rotation_x(pitch_delta.to_radians()) * eye_transform.rotation)
+                        .normalize();
+                }
+                transform.rotation = (Quat::from_rotation_y(yaw_delta.to_radians()) * transform.rotation)
+                    .normalize();
+            },
+            None => {
+                let pitch_delta = clamp_pitch_delta(&transform, pitch_delta, &look_settings);
+                let new_rotation = Quat::from_rotation_y(yaw_delta.to_radians())
+                    * Quat::from_axis_angle(transform.local_x(), pitch_delta.to_radians())
+                    * transform.rotation;
+
+                transform.rotation = new_rotation.normalize();
+            },
+        }
     });
 }
 
-fn on_update_look_right(
-    mut player_query: Query<&mut Transform, (With<Possessed>, Without<IsFocusedOnUI>)>,
-    mut actions: EventReader<LookRight>,
-    time: Res<Time>
-) {
-    actions.iter().for_each(|action: &LookRight| {
-        let delta: f32 = (action.0 * time.delta_seconds() * SENSITIVITY).to_radians();
-        player_query.iter_mut().for_each(|mut transform| {
-            let new_rotation = Quat::from_axis_angle(
-                -Vec3::Y,
-                delta
-            ) * transform.rotation;
+/// The change of pitch, within the LookSettings' limits
+fn clamp_pitch_delta(transform: &Transform, pitch_delta: f32, look_settings: &LookSettings) -> f32 {
+    let pitch = (-transform.local_z()).y.clamp(-1.0, 1.0).asin().to_degrees();
+    (pitch + pitch_delta).clamp(look_settings.min_pitch, look_settings.max_pitch) - pitch
+}
 
-            transform.rotation = new_rotation.normalize();
-        });
-    });
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+    use bevy::app::Events;
+
+    use crate::plugins::actions::{Crouch, Jump, LookRight, LookUp, MoveForward, StrafeRight};
+    use crate::plugins::input::{InputSystem, handle_game_input};
+    use crate::plugins::input::bindings::parse_input_bindings;
+    use crate::plugins::input::contexts::{InputContext, InputContexts};
+    use crate::plugins::input::gamepad::ConnectedGamepads;
+    use crate::plugins::input::recording::{
+        FrameTime, InputFrame, InputRecorder, InputRecording, record_or_replay_inputs
+    };
+    use crate::plugins::input::state::{ActionState, KeyHistory, update_action_state};
+    use crate::plugins::player::Possessed;
+    use super::{LookSettings, SpectatorSettings, on_update_look, on_update_move_forward, on_update_move_strafe};
+
+    const BINDINGS: &str = "
+- binding: MoveForward
+  key: W
+- binding: StrafeRight
+  key: D
+- binding: LookRight
+  mouse_axis: X
+";
+
+    const DELTA_SECONDS: f32 = 1.0 / 60.0;
+
+    /// Half a second flying forward, then half a second strafing right
+    fn create_recording() -> InputRecording {
+        let frame = |key: KeyCode| InputFrame {
+            delta_seconds: DELTA_SECONDS,
+            keys: vec![key],
+            ..Default::default()
+        };
+
+        InputRecording {
+            frames: (0..30).map(|_| frame(KeyCode::W))
+                .chain((0..30).map(|_| frame(KeyCode::D)))
+                .collect(),
+        }
+    }
+
+    fn create_app(recording: InputRecording) -> App {
+        let mut input_contexts = InputContexts::default();
+        input_contexts.push(InputContext::Gameplay);
+
+        let mut app = App::build();
+        app
+            .add_plugins(MinimalPlugins)
+            .add_plugin(bevy::input::InputPlugin)
+            .add_event::<LookUp>()
+            .add_event::<LookRight>()
+            .add_event::<MoveForward>()
+            .add_event::<StrafeRight>()
+            .add_event::<Crouch>()
+            .add_event::<Jump>()
+            .insert_resource(parse_input_bindings(BINDINGS.as_bytes()).unwrap())
+            .insert_resource(input_contexts)
+            .insert_resource(InputRecorder::replay(recording))
+            .init_resource::<ActionState>()
+            .init_resource::<ConnectedGamepads>()
+            .init_resource::<KeyHistory>()
+            .init_resource::<FrameTime>()
+            .init_resource::<LookSettings>()
+            .init_resource::<SpectatorSettings>()
+            .init_resource::<LookUpdates>()
+            .add_system_to_stage(CoreStage::PreUpdate, record_or_replay_inputs.system()
+                .after(bevy::input::InputSystem))
+            .add_system(update_action_state.system()
+                .label(InputSystem::UpdateActionState))
+            .add_system(handle_game_input.system()
+                .label("handle_game_input")
+                .after(InputSystem::UpdateActionState))
+            .add_system(on_update_move_forward.system()
+                .after("handle_game_input"))
+            .add_system(on_update_move_strafe.system()
+                .after("handle_game_input"))
+            .add_system(on_update_look.system()
+                .label("on_update_look")
+                .after("handle_game_input"))
+            .add_system(count_look_updates.system()
+                .after("on_update_look"));
+        app.app
+    }
+
+    /// Number of frames in which the Possessed entity's Transform was written to
+    #[derive(Default)]
+    struct LookUpdates(usize);
+
+    fn count_look_updates(
+        player_query: Query<(), (Changed<Transform>, With<Possessed>)>,
+        mut look_updates: ResMut<LookUpdates>,
+    ) {
+        if player_query.iter().next().is_some() {
+            look_updates.0 += 1;
+        }
+    }
+
+    /// Replay the whole recording, returning the final Transform of the Possessed entity
+    fn replay(recording: InputRecording) -> (App, Transform) {
+        let frame_count = recording.frames.len();
+        let mut app = create_app(recording);
+        let player = app.world.spawn()
+            .insert(Transform::identity())
+            .insert(Possessed)
+            .id();
+
+        // One more frame, to release the keys held at the end of the recording
+        (0..=frame_count).for_each(|_| app.update());
+
+        let transform = *app.world.get::<Transform>(player).unwrap();
+        (app, transform)
+    }
+
+    #[test]
+    fn replay_moves_the_possessed_entity() {
+        let (app, transform) = replay(create_recording());
+
+        let distance = SpectatorSettings::default().speed * 30.0 * DELTA_SECONDS;
+        let expected = Vec3::new(distance, 0.0, -distance);
+        assert!((transform.translation - expected).length() < 1e-4,
+            "expected {:?}, got {:?}", expected, transform.translation);
+        assert!(matches!(app.world.get_resource::<InputRecorder>().unwrap(), InputRecorder::Idle));
+    }
+
+    #[test]
+    fn replay_survives_a_round_trip_to_a_file() {
+        let recording = create_recording();
+        let contents = serde_yaml::to_string(&recording).unwrap();
+        let loaded: InputRecording = serde_yaml::from_str(&contents).unwrap();
+        assert_eq!(loaded, recording);
+
+        let (_, expected) = replay(recording);
+        let (_, actual) = replay(loaded);
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn smoothed_look_motion_stops() {
+        let frame = |mouse_motion: [f32; 2]| InputFrame {
+            delta_seconds: DELTA_SECONDS,
+            mouse_motion,
+            ..Default::default()
+        };
+        let recording = InputRecording {
+            frames: (0..10).map(|_| frame([5.0, 0.0]))
+                .chain((0..590).map(|_| frame([0.0, 0.0])))
+                .collect(),
+        };
+
+        let mut app = create_app(recording);
+        app.world.insert_resource(LookSettings { smoothing: 0.1, ..Default::default() });
+        app.world.spawn()
+            .insert(Transform::identity())
+            .insert(Possessed);
+
+        // The smoothed rate decays for a while after the mouse stops, but not forever
+        (0..=600).for_each(|_| app.update());
+        let look_updates = app.world.get_resource::<LookUpdates>().unwrap().0;
+        assert!(look_updates > 10 && look_updates < 300, "{} look updates", look_updates);
+    }
+
+    #[test]
+    fn pitch_is_clamped() {
+        let mut app = App::build();
+        app
+            .add_event::<LookUp>()
+            .add_event::<LookRight>()
+            .init_resource::<LookSettings>()
+            .insert_resource(FrameTime { delta_seconds: DELTA_SECONDS, seconds_since_startup: 0.0 })
+            .add_system(on_update_look.system());
+        let mut app = app.app;
+        let player = app.world.spawn()
+            .insert(Transform::identity())
+            .insert(Possessed)
+            .id();
+
+        // Way more than a half-turn upwards
+        app.world.get_resource_mut::<Events<LookUp>>().unwrap().send(LookUp(-10000.0));
+        app.update();
+
+        let forward = -app.world.get::<Transform>(player).unwrap().local_z();
+        let pitch = forward.y.asin().to_degrees();
+        let max_pitch = LookSettings::default().max_pitch;
+        assert!((pitch - max_pitch).abs() < 1e-2, "expected {}, got {}", max_pitch, pitch);
+    }
 }
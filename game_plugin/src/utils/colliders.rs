@@ -0,0 +1,165 @@
+/// Colliders matching what gets rendered: derive a heron CollisionShape from a `shape::*` descriptor or a Mesh.
+///     Heron colliders have no offset, so every shape is centered on its entity's origin, like bevy's shapes.
+///     Heron 0.12 has no triangle mesh collider: concave meshes only get their convex hull until it does.
+use bevy::prelude::*;
+use bevy::render::mesh::VertexAttributeValues;
+use heron::prelude::*;
+
+
+/// Keeps flat shapes (planes, quads) from being infinitely thin
+const MIN_HALF_EXTENT: f32 = 0.01;
+
+
+/// Anything a matching collider can be derived from
+pub trait ToCollisionShape {
+    /// The collider of this shape, once scaled like its entity's Transform
+    fn collision_shape(&self, scale: Vec3) -> CollisionShape;
+}
+
+impl ToCollisionShape for shape::Cube {
+    fn collision_shape(&self, scale: Vec3) -> CollisionShape {
+        cuboid(Vec3::splat(self.size / 2.0) * scale)
+    }
+}
+
+impl ToCollisionShape for shape::Box {
+    /// Bevy's boxes can be off-center, but colliders can't: the box is treated as centered
+    fn collision_shape(&self, scale: Vec3) -> CollisionShape {
+        let size = Vec3::new(self.max_x - self.min_x, self.max_y - self.min_y, self.max_z - self.min_z);
+        cuboid(size / 2.0 * scale)
+    }
+}
+
+impl ToCollisionShape for shape::Plane {
+    fn collision_shape(&self, scale: Vec3) -> CollisionShape {
+        cuboid(Vec3::new(self.size / 2.0, 0.0, self.size / 2.0) * scale)
+    }
+}
+
+impl ToCollisionShape for shape::Quad {
+    fn collision_shape(&self, scale: Vec3) -> CollisionShape {
+        cuboid(Vec3::new(self.size.x / 2.0, self.size.y / 2.0, 0.0) * scale)
+    }
+}
+
+impl ToCollisionShape for shape::Icosphere {
+    /// A sphere can't be stretched: non-uniform scales use their largest axis
+    fn collision_shape(&self, scale: Vec3) -> CollisionShape {
+        CollisionShape::Sphere { radius: self.radius * scale.abs().max_element() }
+    }
+}
+
+impl ToCollisionShape for shape::Capsule {
+    /// Upright, like bevy's capsule: the height follows the Y scale, the radius the largest of X and Z
+    fn collision_shape(&self, scale: Vec3) -> CollisionShape {
+        CollisionShape::Capsule {
+            half_segment: self.depth / 2.0 * scale.y.abs(),
+            radius: self.radius * scale.x.abs().max(scale.z.abs()),
+        }
+    }
+}
+
+impl ToCollisionShape for shape::Torus {
+    fn collision_shape(&self, scale: Vec3) -> CollisionShape {
+        mesh_collision_shape(&Mesh::from(*self), MeshCollider::ConvexHull, scale)
+            .expect("bevy's torus has vertex positions")
+    }
+}
+
+
+/// How closely a collider derived from a Mesh follows it: spheres and capsules come from their `shape::*` instead
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MeshCollider {
+    /// The smallest box around the origin containing every vertex
+    Cuboid,
+    /// The smallest convex shape containing every vertex
+    ConvexHull,
+}
+
+/// The collider of a mesh, once scaled: `None` if the mesh has no vertex positions
+pub fn mesh_collision_shape(mesh: &Mesh, kind: MeshCollider, scale: Vec3) -> Option<CollisionShape> {
+    let points: Vec<Vec3> = match mesh.attribute(Mesh::ATTRIBUTE_POSITION)? {
+        VertexAttributeValues::Float3(positions) => positions.iter()
+            .map(|it| Vec3::from(*it) * scale)
+            .collect(),
+        _default => return None,
+    };
+    if points.is_empty() {
+        return None;
+    }
+
+    let shape = match kind {
+        MeshCollider::Cuboid => {
+            let half_extents = points.iter().fold(Vec3::ZERO, |extents, it| extents.max(it.abs()));
+            cuboid(half_extents)
+        },
+        MeshCollider::ConvexHull => CollisionShape::ConvexHull { points, border_radius: None },
+    };
+    Some(shape)
+}
+
+
+/// Helpers
+fn cuboid(half_extents: Vec3) -> CollisionShape {
+    CollisionShape::Cuboid {
+        half_extends: half_extents.abs().max(Vec3::splat(MIN_HALF_EXTENT)),
+        border_radius: None,
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+    use heron::prelude::*;
+
+    use super::{MeshCollider, ToCollisionShape, mesh_collision_shape};
+
+    fn half_extents(shape: CollisionShape) -> Vec3 {
+        match shape {
+            CollisionShape::Cuboid { half_extends, .. } => half_extends,
+            _default => panic!("Not a cuboid"),
+        }
+    }
+
+    #[test]
+    fn cubes_get_a_matching_cuboid() {
+        let cube = shape::Cube::default();
+        assert_eq!(half_extents(cube.collision_shape(Vec3::ONE)), Vec3::splat(0.5));
+        assert_eq!(half_extents(cube.collision_shape(Vec3::new(2.0, 1.0, 4.0))), Vec3::new(1.0, 0.5, 2.0));
+
+        let mesh = Mesh::from(cube);
+        let derived = mesh_collision_shape(&mesh, MeshCollider::Cuboid, Vec3::splat(2.0)).unwrap();
+        assert_eq!(half_extents(derived), Vec3::splat(1.0));
+    }
+
+    #[test]
+    fn scale_is_applied_to_round_shapes() {
+        let sphere = shape::Icosphere { radius: 1.0, subdivisions: 2 };
+        match sphere.collision_shape(Vec3::new(1.0, 3.0, 1.0)) {
+            CollisionShape::Sphere { radius } => assert_eq!(radius, 3.0),
+            _default => panic!("Not a sphere"),
+        }
+
+        let capsule = shape::Capsule { radius: 0.5, depth: 1.0, ..Default::default() };
+        match capsule.collision_shape(Vec3::new(2.0, 2.0, 1.0)) {
+            CollisionShape::Capsule { half_segment, radius } => assert_eq!((half_segment, radius), (1.0, 1.0)),
+            _default => panic!("Not a capsule"),
+        }
+    }
+
+    #[test]
+    fn convex_hulls_keep_every_vertex() {
+        let mesh = Mesh::from(shape::Cube::default());
+        match mesh_collision_shape(&mesh, MeshCollider::ConvexHull, Vec3::splat(2.0)).unwrap() {
+            CollisionShape::ConvexHull { points, .. } => {
+                assert_eq!(points.len(), mesh.count_vertices());
+                assert!(points.iter().all(|it| it.abs() == Vec3::ONE));
+            },
+            _default => panic!("Not a convex hull"),
+        }
+
+        assert!(mesh_collision_shape(&Mesh::new(bevy::render::pipeline::PrimitiveTopology::TriangleList),
+            MeshCollider::ConvexHull, Vec3::ONE).is_none());
+    }
+}
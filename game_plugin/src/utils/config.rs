@@ -0,0 +1,50 @@
+use std::path::PathBuf;
+
+/// Name of the per-user configuration directory
+// ToDo | Use your own game name
+const GAME_DIRECTORY: &str = "bevy_game";
+
+/// Directory for per-user configuration files, e.g. `~/.config/bevy_game` on Linux.
+///     Returns None when no such directory can be determined (e.g. on the web).
+pub fn get_user_config_dir() -> Option<PathBuf> {
+    let base_dir: Option<PathBuf> = if cfg!(target_os = "windows") {
+        std::env::var_os("APPDATA").map(PathBuf::from)
+    } else if cfg!(target_os = "macos") {
+        std::env::var_os("HOME")
+            .map(|it| PathBuf::from(it).join("Library").join("Application Support"))
+    } else {
+        std::env::var_os("XDG_CONFIG_HOME").map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|it| PathBuf::from(it).join(".config")))
+    };
+
+    base_dir.map(|it| it.join(GAME_DIRECTORY))
+}
+
+/// Path of a per-user configuration file
+pub fn get_user_config_file(file_name: &str) -> Option<PathBuf> {
+    get_user_config_dir().map(|it| it.join(file_name))
+}
+
+/// Write a per-user configuration file, creating its directory if needed
+pub fn write_user_config_file(file_name: &str, contents: &str) -> std::io::Result<PathBuf> {
+    let path = get_user_config_file(file_name).ok_or_else(|| std::io::Error::new(
+        std::io::ErrorKind::NotFound,
+        "No user configuration directory is available"
+    ))?;
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(&path, contents)?;
+    Ok(path)
+}
+
+/// The game's `assets` directory: next to the crate's manifest when run with cargo, else next to the executable
+pub fn get_assets_dir() -> Option<PathBuf> {
+    let base_dir: Option<PathBuf> = std::env::var_os("CARGO_MANIFEST_DIR")
+        .map(PathBuf::from)
+        .or_else(|| std::env::current_exe().ok()
+            .and_then(|it| it.parent().map(PathBuf::from)));
+
+    base_dir.map(|it| it.join("assets"))
+}
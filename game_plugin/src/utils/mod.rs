@@ -1,2 +1,3 @@
+pub mod colliders;
 pub mod random_color;
-pub mod keys;
\ No newline at end of file
+pub mod config;
\ No newline at end of file
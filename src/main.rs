@@ -4,8 +4,10 @@
 #[cfg(target_arch = "wasm32")]
 use bevy_webgl2;
 
-use bevy::prelude::{App, ClearColor, Color, WindowDescriptor};
+use bevy::prelude::{App, WindowDescriptor};
 use bevy::DefaultPlugins;
+#[cfg(not(target_arch = "wasm32"))]
+use bevy::log::LogPlugin;
 use game_plugin::GamePlugin;
 // use mesh_demo_plugin::meshes::MeshDemoPlugin;
 
@@ -13,14 +15,21 @@ fn main() {
     let mut app = App::build();
     app
         // .insert_resource(Msaa { samples: 4 })
-        .insert_resource(ClearColor(Color::rgb(0.2, 0.4, 0.4)))
         .insert_resource(WindowDescriptor {
             width: 800.,
             height: 600.,
             title: "Bevy game".to_string(), // ToDo
             ..Default::default()
-        })
-        .add_plugins(DefaultPlugins)
+        });
+
+    // The game's ConsolePlugin installs its own logger, which also prints to the in-game console.
+    //  On the web, Bevy's LogPlugin is kept: it logs to the browser console.
+    #[cfg(not(target_arch = "wasm32"))]
+    app.add_plugins_with(DefaultPlugins, |group| group.disable::<LogPlugin>());
+    #[cfg(target_arch = "wasm32")]
+    app.add_plugins(DefaultPlugins);
+
+    app
         .add_plugin(GamePlugin)
         // .add_plugin(MeshDemoPlugin)
     ;